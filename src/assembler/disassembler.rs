@@ -0,0 +1,191 @@
+use crate::virtual_machine::instructions::{
+    ADD, AND, BR, JMP, JSR, LD, LDI, LDR, LEA, NOT, RES, RTI, ST, STI, STR, TRAP,
+};
+
+use crate::loader::{error::LoadError, segment::Segment};
+
+use super::symbol_table::SymbolTable;
+
+const RETURN_REGISTER: u16 = 7;
+
+pub fn disassemble(instruction: u16, address: u16, symbol_table: &SymbolTable) -> String {
+    let destination_register = register_name(instruction >> 9);
+    let source_register = register_name(instruction >> 6);
+    let target = |amount_of_bits| {
+        let offset = sign_extend(instruction, amount_of_bits);
+        symbol_table.format_address(address.wrapping_add(1).wrapping_add(offset as u16))
+    };
+    let opcode = instruction >> 12;
+    match opcode {
+        BR => match (instruction >> 9) & 0b111 {
+            0 => "NOP".to_string(),
+            condition => {
+                let flags: String = [(0b100, 'n'), (0b010, 'z'), (0b001, 'p')]
+                    .iter()
+                    .filter(|(flag, _)| condition & flag != 0)
+                    .map(|(_, name)| name)
+                    .collect();
+                format!("BR{flags} {}", target(9))
+            }
+        },
+        ADD | AND => {
+            let name = if opcode == ADD { "ADD" } else { "AND" };
+            if instruction & (1 << 5) != 0 {
+                format!(
+                    "{name} {destination_register}, {source_register}, #{}",
+                    sign_extend(instruction, 5)
+                )
+            } else {
+                format!(
+                    "{name} {destination_register}, {source_register}, {}",
+                    register_name(instruction)
+                )
+            }
+        }
+        LD | LDI | LEA | ST | STI => {
+            let name = match opcode {
+                LD => "LD",
+                LDI => "LDI",
+                LEA => "LEA",
+                ST => "ST",
+                _ => "STI",
+            };
+            format!("{name} {destination_register}, {}", target(9))
+        }
+        LDR | STR => {
+            let name = if opcode == LDR { "LDR" } else { "STR" };
+            format!(
+                "{name} {destination_register}, {source_register}, #{}",
+                sign_extend(instruction, 6)
+            )
+        }
+        JSR if instruction & (1 << 11) != 0 => format!("JSR {}", target(11)),
+        JSR => format!("JSRR {source_register}"),
+        JMP if (instruction >> 6) & 0b111 == RETURN_REGISTER => "RET".to_string(),
+        JMP => format!("JMP {source_register}"),
+        NOT => format!("NOT {destination_register}, {source_register}"),
+        RTI => "RTI".to_string(),
+        TRAP => match instruction & 0xFF {
+            0x20 => "GETC".to_string(),
+            0x21 => "OUT".to_string(),
+            0x22 => "PUTS".to_string(),
+            0x23 => "IN".to_string(),
+            0x24 => "PUTSP".to_string(),
+            0x25 => "HALT".to_string(),
+            trap_vector => format!("TRAP x{trap_vector:02X}"),
+        },
+        RES => format!(".FILL x{instruction:04X}"),
+        _ => unreachable!("opcodes are four bits"),
+    }
+}
+
+/// One line per word: address, raw word, label (when there are symbols) and the instruction.
+pub fn disassemble_program(origin: u16, words: &[u16], symbol_table: &SymbolTable) -> Vec<String> {
+    words
+        .iter()
+        .enumerate()
+        .map(|(offset, word)| {
+            let address = origin.wrapping_add(offset as u16);
+            let instruction = disassemble(*word, address, symbol_table);
+            if symbol_table.is_empty() {
+                return format!("x{address:04X}  x{word:04X}  {instruction}");
+            }
+            let label = symbol_table.label_at(address).unwrap_or_default();
+            format!("x{address:04X}  x{word:04X}  {label:<16}  {instruction}")
+        })
+        .collect()
+}
+
+/// Lists a program image, with labels when there is a `.sym` file next to it.
+pub fn disassemble_file(file: &str) -> Result<Vec<String>, LoadError> {
+    let segment = Segment::read(file, None)?;
+    let symbol_table =
+        SymbolTable::load_for_object_file(file).map_err(|error| LoadError::Read {
+            file: file.to_string(),
+            error,
+        })?;
+    Ok(disassemble_program(
+        segment.origin,
+        &segment.words,
+        &symbol_table,
+    ))
+}
+
+fn register_name(bits: u16) -> String {
+    format!("R{}", bits & 0b111)
+}
+
+fn sign_extend(instruction: u16, amount_of_bits: u32) -> i16 {
+    let shift = 16 - amount_of_bits;
+    ((instruction << shift) as i16) >> shift
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::{disassemble, disassemble_program};
+    use crate::assembler::{lc3_assembler::assemble, symbol_table::SymbolTable};
+
+    #[test]
+    fn can_disassemble_operate_and_trap_instructions() {
+        let symbol_table = SymbolTable::new();
+
+        assert_eq!(disassemble(0x1283, 0x3000, &symbol_table), "ADD R1, R2, R3");
+        assert_eq!(
+            disassemble(0x12BF, 0x3000, &symbol_table),
+            "ADD R1, R2, #-1"
+        );
+        assert_eq!(
+            disassemble(0x65BE, 0x3000, &symbol_table),
+            "LDR R2, R6, #-2"
+        );
+        assert_eq!(disassemble(0xC1C0, 0x3000, &symbol_table), "RET");
+        assert_eq!(disassemble(0xF025, 0x3000, &symbol_table), "HALT");
+        assert_eq!(disassemble(0xF030, 0x3000, &symbol_table), "TRAP x30");
+        assert_eq!(disassemble(0xD000, 0x3000, &symbol_table), ".FILL xD000");
+    }
+
+    #[test]
+    fn pc_relative_targets_are_resolved() {
+        let mut symbol_table = SymbolTable::new();
+        symbol_table.insert("LOOP", 0x3000);
+
+        assert_eq!(
+            disassemble(0x0FFB, 0x3000, &SymbolTable::new()),
+            "BRnzp x2FFC"
+        );
+        assert_eq!(
+            disassemble(0x03FE, 0x3001, &symbol_table),
+            "BRp x3000 <LOOP>"
+        );
+        assert_eq!(
+            disassemble(0x4801, 0x3000, &symbol_table),
+            "JSR x3002 <LOOP+2>"
+        );
+    }
+
+    #[test]
+    fn disassembling_an_assembled_program_gives_back_its_source() {
+        let source = "
+            .ORIG x3000
+    LOOP    ADD R0, R0, #-1
+            BRp LOOP
+            HALT
+            .END
+        ";
+        let object_file = assemble(source).unwrap();
+        let listing = disassemble_program(
+            object_file.origin,
+            &object_file.words,
+            &object_file.symbol_table,
+        );
+
+        assert_eq!(
+            listing,
+            vec![
+                "x3000  x103F  LOOP              ADD R0, R0, #-1",
+                "x3001  x03FE                    BRp x3000 <LOOP>",
+                "x3002  xF025                    HALT",
+            ]
+        );
+    }
+}
@@ -0,0 +1,137 @@
+use std::{fmt::Display, io::Error};
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum AssemblyErrorKind {
+    UnexpectedCharacter(char),
+    UnterminatedString,
+    InvalidEscape(char),
+    InvalidNumber(String),
+    UnknownInstruction(String),
+    ExpectedInstruction,
+    WrongNumberOfOperands {
+        expected: usize,
+        found: usize,
+    },
+    MisplacedComma,
+    ExpectedRegister,
+    ExpectedNumber,
+    ExpectedLabelOrOffset,
+    ExpectedString,
+    ValueOutOfRange {
+        value: i32,
+        minimum: i32,
+        maximum: i32,
+    },
+    UndefinedLabel(String),
+    DuplicateLabel(String),
+    MissingOrigin,
+    DuplicateOrigin,
+    AddressSpaceOverflow,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct AssemblyError {
+    pub line: usize,
+    pub column: usize,
+    pub kind: AssemblyErrorKind,
+}
+
+impl AssemblyError {
+    pub fn new(line: usize, column: usize, kind: AssemblyErrorKind) -> Self {
+        Self { line, column, kind }
+    }
+}
+
+impl Display for AssemblyErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AssemblyErrorKind::UnexpectedCharacter(char) => {
+                write!(f, "unexpected character {char:?}")
+            }
+            AssemblyErrorKind::UnterminatedString => write!(f, "unterminated string"),
+            AssemblyErrorKind::InvalidEscape(char) => {
+                write!(f, "invalid escape sequence \\{char}")
+            }
+            AssemblyErrorKind::InvalidNumber(number) => write!(f, "invalid number {number}"),
+            AssemblyErrorKind::UnknownInstruction(name) => {
+                write!(f, "unknown instruction {name}")
+            }
+            AssemblyErrorKind::ExpectedInstruction => write!(f, "expected an instruction"),
+            AssemblyErrorKind::WrongNumberOfOperands { expected, found } => {
+                write!(f, "expected {expected} operands, found {found}")
+            }
+            AssemblyErrorKind::MisplacedComma => write!(f, "misplaced comma"),
+            AssemblyErrorKind::ExpectedRegister => write!(f, "expected a register"),
+            AssemblyErrorKind::ExpectedNumber => write!(f, "expected a number"),
+            AssemblyErrorKind::ExpectedLabelOrOffset => {
+                write!(f, "expected a label or an offset")
+            }
+            AssemblyErrorKind::ExpectedString => write!(f, "expected a string"),
+            AssemblyErrorKind::ValueOutOfRange {
+                value,
+                minimum,
+                maximum,
+            } => write!(
+                f,
+                "value {value} is outside the range {minimum}..={maximum}"
+            ),
+            AssemblyErrorKind::UndefinedLabel(label) => write!(f, "undefined label {label}"),
+            AssemblyErrorKind::DuplicateLabel(label) => {
+                write!(f, "label {label} is already defined")
+            }
+            AssemblyErrorKind::MissingOrigin => write!(f, "code before .ORIG"),
+            AssemblyErrorKind::DuplicateOrigin => write!(f, "more than one .ORIG"),
+            AssemblyErrorKind::AddressSpaceOverflow => {
+                write!(f, "program doesn't fit in the address space")
+            }
+        }
+    }
+}
+
+impl Display for AssemblyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: {}",
+            self.line, self.column, self.kind
+        )
+    }
+}
+
+impl std::error::Error for AssemblyError {}
+
+#[derive(Debug)]
+pub enum AssemblerError {
+    Assembly(AssemblyError),
+    Io(Error),
+}
+
+impl Display for AssemblerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AssemblerError::Assembly(error) => write!(f, "{error}"),
+            AssemblerError::Io(error) => write!(f, "I/O failure: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for AssemblerError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            AssemblerError::Assembly(error) => Some(error),
+            AssemblerError::Io(error) => Some(error),
+        }
+    }
+}
+
+impl From<AssemblyError> for AssemblerError {
+    fn from(error: AssemblyError) -> Self {
+        AssemblerError::Assembly(error)
+    }
+}
+
+impl From<Error> for AssemblerError {
+    fn from(error: Error) -> Self {
+        AssemblerError::Io(error)
+    }
+}
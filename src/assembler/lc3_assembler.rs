@@ -0,0 +1,582 @@
+use std::{fs, path::Path};
+
+use crate::virtual_machine::instructions::{
+    ADD, AND, BR, JMP, JSR, LD, LDI, LDR, LEA, NOT, RTI, ST, STI, STR, TRAP,
+};
+
+use super::{
+    error::{AssemblerError, AssemblyError, AssemblyErrorKind},
+    lexer::{tokenize_line, Token, TokenKind},
+    symbol_table::{SymbolTable, SYMBOL_FILE_EXTENSION},
+};
+
+const ADDRESS_SPACE_SIZE: u32 = 1 << 16;
+const INSTRUCTIONS: [&str; 30] = [
+    "ADD", "AND", "NOT", "JMP", "RET", "JSR", "JSRR", "LD", "LDI", "LDR", "LEA", "ST", "STI",
+    "STR", "TRAP", "RTI", "GETC", "OUT", "PUTS", "IN", "PUTSP", "HALT", "BR", "BRN", "BRZ", "BRP",
+    "BRNZ", "BRNP", "BRZP", "BRNZP",
+];
+const TRAP_ALIASES: [(&str, u16); 6] = [
+    ("GETC", 0x20),
+    ("OUT", 0x21),
+    ("PUTS", 0x22),
+    ("IN", 0x23),
+    ("PUTSP", 0x24),
+    ("HALT", 0x25),
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ObjectFile {
+    pub origin: u16,
+    pub words: Vec<u16>,
+    pub symbol_table: SymbolTable,
+}
+
+impl ObjectFile {
+    /// Same layout `Segment::from_object_bytes` reads: the origin followed by the words, big-endian.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        std::iter::once(self.origin)
+            .chain(self.words.iter().copied())
+            .flat_map(u16::to_be_bytes)
+            .collect()
+    }
+}
+
+struct Statement {
+    line: usize,
+    column: usize,
+    address: u16,
+    mnemonic: String,
+    operands: Vec<Token>,
+}
+
+impl Statement {
+    fn error(&self, column: usize, kind: AssemblyErrorKind) -> AssemblyError {
+        AssemblyError::new(self.line, column, kind)
+    }
+
+    fn expect_operands(&self, expected: usize) -> Result<(), AssemblyError> {
+        if self.operands.len() != expected {
+            return Err(self.error(
+                self.column,
+                AssemblyErrorKind::WrongNumberOfOperands {
+                    expected,
+                    found: self.operands.len(),
+                },
+            ));
+        }
+        Ok(())
+    }
+
+    fn register(&self, index: usize) -> Result<u16, AssemblyError> {
+        let operand = &self.operands[index];
+        match operand.kind {
+            TokenKind::Register(register) => Ok(register),
+            _ => Err(self.error(operand.column, AssemblyErrorKind::ExpectedRegister)),
+        }
+    }
+
+    fn number(&self, index: usize, minimum: i32, maximum: i32) -> Result<i32, AssemblyError> {
+        let operand = &self.operands[index];
+        match operand.kind {
+            TokenKind::Number(value) => check_range(value, minimum, maximum)
+                .map_err(|kind| self.error(operand.column, kind)),
+            _ => Err(self.error(operand.column, AssemblyErrorKind::ExpectedNumber)),
+        }
+    }
+
+    fn immediate(&self, index: usize, amount_of_bits: u32) -> Result<u16, AssemblyError> {
+        let (minimum, maximum) = signed_range(amount_of_bits);
+        let value = self.number(index, minimum, maximum)?;
+        Ok(truncate(value, amount_of_bits))
+    }
+
+    fn pc_offset(
+        &self,
+        index: usize,
+        amount_of_bits: u32,
+        symbol_table: &SymbolTable,
+    ) -> Result<u16, AssemblyError> {
+        let operand = &self.operands[index];
+        let offset = match &operand.kind {
+            TokenKind::Number(offset) => *offset,
+            TokenKind::Identifier(label) => {
+                let target =
+                    lookup(symbol_table, label).map_err(|kind| self.error(operand.column, kind))?;
+                target as i32 - (self.address as i32 + 1)
+            }
+            _ => return Err(self.error(operand.column, AssemblyErrorKind::ExpectedLabelOrOffset)),
+        };
+        let (minimum, maximum) = signed_range(amount_of_bits);
+        check_range(offset, minimum, maximum).map_err(|kind| self.error(operand.column, kind))?;
+        Ok(truncate(offset, amount_of_bits))
+    }
+
+    fn string(&self) -> Result<&str, AssemblyError> {
+        self.expect_operands(1)?;
+        let operand = &self.operands[0];
+        match &operand.kind {
+            TokenKind::String(string) => Ok(string),
+            _ => Err(self.error(operand.column, AssemblyErrorKind::ExpectedString)),
+        }
+    }
+
+    fn size(&self) -> Result<u32, AssemblyError> {
+        match self.mnemonic.as_str() {
+            ".BLKW" => {
+                self.expect_operands(1)?;
+                Ok(self.number(0, 0, u16::MAX as i32)? as u32)
+            }
+            ".STRINGZ" => Ok(self.string()?.chars().count() as u32 + 1),
+            _ => Ok(1),
+        }
+    }
+
+    fn encode(
+        &self,
+        symbol_table: &SymbolTable,
+        words: &mut Vec<u16>,
+    ) -> Result<(), AssemblyError> {
+        let mnemonic = self.mnemonic.as_str();
+        let word = match mnemonic {
+            "ADD" | "AND" => {
+                self.expect_operands(3)?;
+                let opcode = if mnemonic == "ADD" { ADD } else { AND };
+                let operation = opcode << 12 | self.register(0)? << 9 | self.register(1)? << 6;
+                match self.operands[2].kind {
+                    TokenKind::Register(register) => operation | register,
+                    _ => operation | 1 << 5 | self.immediate(2, 5)?,
+                }
+            }
+            "NOT" => {
+                self.expect_operands(2)?;
+                NOT << 12 | self.register(0)? << 9 | self.register(1)? << 6 | 0b11_1111
+            }
+            "JMP" | "JSRR" => {
+                self.expect_operands(1)?;
+                let opcode = if mnemonic == "JMP" { JMP } else { JSR };
+                opcode << 12 | self.register(0)? << 6
+            }
+            "RET" => {
+                self.expect_operands(0)?;
+                JMP << 12 | 7 << 6
+            }
+            "JSR" => {
+                self.expect_operands(1)?;
+                JSR << 12 | 1 << 11 | self.pc_offset(0, 11, symbol_table)?
+            }
+            "LD" | "LDI" | "LEA" | "ST" | "STI" => {
+                self.expect_operands(2)?;
+                let opcode = match mnemonic {
+                    "LD" => LD,
+                    "LDI" => LDI,
+                    "LEA" => LEA,
+                    "ST" => ST,
+                    _ => STI,
+                };
+                opcode << 12 | self.register(0)? << 9 | self.pc_offset(1, 9, symbol_table)?
+            }
+            "LDR" | "STR" => {
+                self.expect_operands(3)?;
+                let opcode = if mnemonic == "LDR" { LDR } else { STR };
+                opcode << 12
+                    | self.register(0)? << 9
+                    | self.register(1)? << 6
+                    | self.immediate(2, 6)?
+            }
+            "TRAP" => {
+                self.expect_operands(1)?;
+                TRAP << 12 | self.number(0, 0, u8::MAX as i32)? as u16
+            }
+            "RTI" => {
+                self.expect_operands(0)?;
+                RTI << 12
+            }
+            ".FILL" => {
+                self.expect_operands(1)?;
+                let operand = &self.operands[0];
+                match &operand.kind {
+                    TokenKind::Identifier(label) => lookup(symbol_table, label)
+                        .map_err(|kind| self.error(operand.column, kind))?,
+                    _ => self.number(0, i16::MIN as i32, u16::MAX as i32)? as u16,
+                }
+            }
+            ".BLKW" => {
+                words.extend(std::iter::repeat_n(0, self.size()? as usize));
+                return Ok(());
+            }
+            ".STRINGZ" => {
+                let operand = &self.operands[0];
+                for char in self.string()?.chars() {
+                    let char = u16::try_from(char as u32).map_err(|_| {
+                        self.error(operand.column, AssemblyErrorKind::UnexpectedCharacter(char))
+                    })?;
+                    words.push(char);
+                }
+                words.push(0);
+                return Ok(());
+            }
+            _ => {
+                if let Some(condition) = branch_condition(mnemonic) {
+                    self.expect_operands(1)?;
+                    BR << 12 | condition << 9 | self.pc_offset(0, 9, symbol_table)?
+                } else if let Some((_, trap_vector)) =
+                    TRAP_ALIASES.iter().find(|(alias, _)| *alias == mnemonic)
+                {
+                    self.expect_operands(0)?;
+                    TRAP << 12 | trap_vector
+                } else {
+                    return Err(self.error(
+                        self.column,
+                        AssemblyErrorKind::UnknownInstruction(self.mnemonic.clone()),
+                    ));
+                }
+            }
+        };
+        words.push(word);
+        Ok(())
+    }
+}
+
+fn signed_range(amount_of_bits: u32) -> (i32, i32) {
+    (
+        -(1 << (amount_of_bits - 1)),
+        (1 << (amount_of_bits - 1)) - 1,
+    )
+}
+
+fn check_range(value: i32, minimum: i32, maximum: i32) -> Result<i32, AssemblyErrorKind> {
+    if value < minimum || value > maximum {
+        return Err(AssemblyErrorKind::ValueOutOfRange {
+            value,
+            minimum,
+            maximum,
+        });
+    }
+    Ok(value)
+}
+
+fn truncate(value: i32, amount_of_bits: u32) -> u16 {
+    value as u16 & ((1 << amount_of_bits) - 1) as u16
+}
+
+fn lookup(symbol_table: &SymbolTable, label: &str) -> Result<u16, AssemblyErrorKind> {
+    symbol_table
+        .address_of(label)
+        .ok_or_else(|| AssemblyErrorKind::UndefinedLabel(label.to_string()))
+}
+
+fn branch_condition(mnemonic: &str) -> Option<u16> {
+    let flags = mnemonic.strip_prefix("BR")?;
+    if flags.is_empty() {
+        return Some(0b111);
+    }
+    if !["N", "Z", "P", "NZ", "NP", "ZP", "NZP"].contains(&flags) {
+        return None;
+    }
+    Some(flags.chars().fold(0, |condition, flag| {
+        condition
+            | match flag {
+                'N' => 0b100,
+                'Z' => 0b010,
+                _ => 0b001,
+            }
+    }))
+}
+
+fn is_instruction(word: &str) -> bool {
+    let word = word.to_ascii_uppercase();
+    word.starts_with('.') || INSTRUCTIONS.contains(&word.as_str())
+}
+
+fn remove_commas(tokens: &[Token], line: usize) -> Result<Vec<Token>, AssemblyError> {
+    let mut operands = Vec::new();
+    let mut expects_operand = true;
+    for token in tokens {
+        match token.kind {
+            TokenKind::Comma if expects_operand => {
+                return Err(AssemblyError::new(
+                    line,
+                    token.column,
+                    AssemblyErrorKind::MisplacedComma,
+                ))
+            }
+            TokenKind::Comma => expects_operand = true,
+            _ => {
+                operands.push(token.clone());
+                expects_operand = false;
+            }
+        }
+    }
+    if let (true, Some(comma)) = (expects_operand, tokens.last()) {
+        return Err(AssemblyError::new(
+            line,
+            comma.column,
+            AssemblyErrorKind::MisplacedComma,
+        ));
+    }
+    Ok(operands)
+}
+
+pub fn assemble(source: &str) -> Result<ObjectFile, AssemblyError> {
+    let mut origin = None;
+    let mut location_counter: u32 = 0;
+    let mut symbol_table = SymbolTable::new();
+    let mut statements = Vec::new();
+
+    for (index, line) in source.lines().enumerate() {
+        let line_number = index + 1;
+        let tokens = tokenize_line(line, line_number)?;
+        let mut tokens = tokens.as_slice();
+        if let [Token {
+            kind: TokenKind::Identifier(label),
+            column,
+        }, rest @ ..] = tokens
+        {
+            if !is_instruction(label) {
+                if origin.is_none() {
+                    return Err(AssemblyError::new(
+                        line_number,
+                        *column,
+                        AssemblyErrorKind::MissingOrigin,
+                    ));
+                }
+                if location_counter >= ADDRESS_SPACE_SIZE {
+                    return Err(AssemblyError::new(
+                        line_number,
+                        *column,
+                        AssemblyErrorKind::AddressSpaceOverflow,
+                    ));
+                }
+                if !symbol_table.insert(label, location_counter as u16) {
+                    return Err(AssemblyError::new(
+                        line_number,
+                        *column,
+                        AssemblyErrorKind::DuplicateLabel(label.clone()),
+                    ));
+                }
+                tokens = rest;
+            }
+        }
+        let Some((instruction, operands)) = tokens.split_first() else {
+            continue;
+        };
+        let mnemonic = match &instruction.kind {
+            TokenKind::Identifier(mnemonic) if is_instruction(mnemonic) => {
+                mnemonic.to_ascii_uppercase()
+            }
+            TokenKind::Identifier(mnemonic) => {
+                return Err(AssemblyError::new(
+                    line_number,
+                    instruction.column,
+                    AssemblyErrorKind::UnknownInstruction(mnemonic.clone()),
+                ))
+            }
+            _ => {
+                return Err(AssemblyError::new(
+                    line_number,
+                    instruction.column,
+                    AssemblyErrorKind::ExpectedInstruction,
+                ))
+            }
+        };
+        let statement = Statement {
+            line: line_number,
+            column: instruction.column,
+            address: location_counter as u16,
+            mnemonic,
+            operands: remove_commas(operands, line_number)?,
+        };
+        match statement.mnemonic.as_str() {
+            ".ORIG" => {
+                if origin.is_some() {
+                    return Err(
+                        statement.error(statement.column, AssemblyErrorKind::DuplicateOrigin)
+                    );
+                }
+                statement.expect_operands(1)?;
+                let start = statement.number(0, 0, u16::MAX as i32)? as u16;
+                origin = Some(start);
+                location_counter = start as u32;
+                continue;
+            }
+            ".END" => break,
+            _ => {}
+        }
+        if origin.is_none() {
+            return Err(statement.error(statement.column, AssemblyErrorKind::MissingOrigin));
+        }
+        location_counter += statement.size()?;
+        if location_counter > ADDRESS_SPACE_SIZE {
+            return Err(statement.error(statement.column, AssemblyErrorKind::AddressSpaceOverflow));
+        }
+        statements.push(statement);
+    }
+
+    let origin =
+        origin.ok_or_else(|| AssemblyError::new(1, 1, AssemblyErrorKind::MissingOrigin))?;
+    let mut words = Vec::new();
+    for statement in &statements {
+        statement.encode(&symbol_table, &mut words)?;
+    }
+    Ok(ObjectFile {
+        origin,
+        words,
+        symbol_table,
+    })
+}
+
+pub fn assemble_file(
+    source_path: impl AsRef<Path>,
+    object_path: impl AsRef<Path>,
+) -> Result<(), AssemblerError> {
+    let source = fs::read_to_string(source_path)?;
+    let object_file = assemble(&source)?;
+    fs::write(&object_path, object_file.to_bytes())?;
+    object_file
+        .symbol_table
+        .save(object_path.as_ref().with_extension(SYMBOL_FILE_EXTENSION))?;
+    Ok(())
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::assemble;
+    use crate::{
+        assembler::error::AssemblyErrorKind,
+        virtual_machine::{console::BufferConsole, lc3_virtual_machine::LC3VirtualMachine},
+    };
+
+    #[test]
+    fn can_assemble_every_opcode() {
+        let source = "
+            .ORIG x3000
+            ADD R1, R2, R3
+            ADD R1, R2, #-1
+            AND R0, R0, #0
+            NOT R4, R5
+            BRnzp #-5
+            JMP R2
+            RET
+            JSR #1
+            JSRR R3
+            LD R0, #1
+            LDI R1, #-1
+            LDR R2, R6, #-2
+            LEA R3, #0
+            ST R4, #2
+            STI R5, #3
+            STR R6, R5, #31
+            TRAP x25
+            RTI
+            .END
+        ";
+        let object_file = assemble(source).unwrap();
+
+        assert_eq!(object_file.origin, 0x3000);
+        assert_eq!(
+            object_file.words,
+            vec![
+                0x1283, 0x12BF, 0x5020, 0x997F, 0x0FFB, 0xC080, 0xC1C0, 0x4801, 0x40C0, 0x2001,
+                0xA3FF, 0x65BE, 0xE600, 0x3802, 0xBA03, 0x7D5F, 0xF025, 0x8000,
+            ]
+        );
+    }
+
+    #[test]
+    fn labels_resolve_to_pc_relative_offsets() {
+        let source = "
+            .ORIG x3000
+    LOOP    ADD R0, R0, #-1
+            BRp LOOP
+            LD R1, DATA
+            halt
+    DATA    .FILL LOOP
+            .END
+        ";
+        let object_file = assemble(source).unwrap();
+
+        assert_eq!(
+            object_file.words,
+            vec![0x103F, 0x03FE, 0x2201, 0xF025, 0x3000]
+        );
+    }
+
+    #[test]
+    fn can_assemble_pseudo_ops_to_the_object_format() {
+        let source = ".ORIG x4000\n.STRINGZ \"hi\"\n.BLKW 2\n.FILL #-1\n.END\n";
+        let object_file = assemble(source).unwrap();
+
+        assert_eq!(
+            object_file.to_bytes(),
+            vec![0x40, 0x00, 0x00, b'h', 0x00, b'i', 0, 0, 0, 0, 0, 0, 0xFF, 0xFF]
+        );
+    }
+
+    #[test]
+    fn errors_point_at_the_offending_operand() {
+        let error = assemble(".ORIG x3000\n  BRz NOWHERE\n.END").unwrap_err();
+        assert_eq!((error.line, error.column), (2, 7));
+        assert_eq!(
+            error.kind,
+            AssemblyErrorKind::UndefinedLabel("NOWHERE".to_string())
+        );
+
+        let error = assemble(".ORIG x3000\nADD R0, R0, #16\n.END").unwrap_err();
+        assert_eq!((error.line, error.column), (2, 13));
+        assert_eq!(
+            error.kind,
+            AssemblyErrorKind::ValueOutOfRange {
+                value: 16,
+                minimum: -16,
+                maximum: 15
+            }
+        );
+    }
+
+    #[test]
+    fn structural_errors_are_reported() {
+        let error = assemble("ADD R0, R0, R0").unwrap_err();
+        assert_eq!(error.kind, AssemblyErrorKind::MissingOrigin);
+
+        let error = assemble(".ORIG x3000\nA HALT\nA HALT\n").unwrap_err();
+        assert_eq!((error.line, error.column), (3, 1));
+        assert_eq!(
+            error.kind,
+            AssemblyErrorKind::DuplicateLabel("A".to_string())
+        );
+
+        let error = assemble(".ORIG x3000\nNOT R0\n").unwrap_err();
+        assert_eq!(
+            error.kind,
+            AssemblyErrorKind::WrongNumberOfOperands {
+                expected: 2,
+                found: 1
+            }
+        );
+    }
+
+    #[test]
+    fn assembled_program_runs_on_the_virtual_machine() {
+        let source = "
+            .ORIG x3000
+            LEA R0, MESSAGE
+            PUTS
+            HALT
+    MESSAGE .STRINGZ \"hello\\n\"
+            .END
+        ";
+        let object_file = assemble(source).unwrap();
+        let console = BufferConsole::default();
+        let mut virtual_machine =
+            LC3VirtualMachine::with_console(object_file.origin, console.clone());
+        for (offset, word) in object_file.words.iter().enumerate() {
+            virtual_machine
+                .memory_write(object_file.origin + offset as u16, *word)
+                .unwrap();
+        }
+        virtual_machine.run().unwrap();
+
+        assert_eq!(console.output_string(), "hello\n");
+    }
+}
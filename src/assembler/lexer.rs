@@ -0,0 +1,232 @@
+use super::error::{AssemblyError, AssemblyErrorKind};
+
+const COMMENT_START: char = ';';
+const STRING_DELIMITER: char = '"';
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TokenKind {
+    Identifier(String),
+    Register(u16),
+    Number(i32),
+    String(String),
+    Comma,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub column: usize,
+}
+
+pub fn tokenize_line(line: &str, line_number: usize) -> Result<Vec<Token>, AssemblyError> {
+    let chars: Vec<char> = line.chars().collect();
+    let mut tokens = Vec::new();
+    let mut index = 0;
+    while index < chars.len() {
+        let char = chars[index];
+        let column = index + 1;
+        if char == COMMENT_START {
+            break;
+        }
+        if char.is_whitespace() {
+            index += 1;
+            continue;
+        }
+        if char == ',' {
+            tokens.push(Token {
+                kind: TokenKind::Comma,
+                column,
+            });
+            index += 1;
+            continue;
+        }
+        if char == STRING_DELIMITER {
+            let (string, end) = read_string(&chars, index, line_number)?;
+            tokens.push(Token {
+                kind: TokenKind::String(string),
+                column,
+            });
+            index = end;
+            continue;
+        }
+        if is_word_char(char) || char == '.' || char == '#' || char == '-' {
+            let start = index;
+            index += 1;
+            while index < chars.len() && (is_word_char(chars[index]) || chars[index] == '-') {
+                index += 1;
+            }
+            let word: String = chars[start..index].iter().collect();
+            let kind = classify_word(&word).ok_or_else(|| {
+                AssemblyError::new(
+                    line_number,
+                    column,
+                    AssemblyErrorKind::InvalidNumber(word.clone()),
+                )
+            })?;
+            tokens.push(Token { kind, column });
+            continue;
+        }
+        return Err(AssemblyError::new(
+            line_number,
+            column,
+            AssemblyErrorKind::UnexpectedCharacter(char),
+        ));
+    }
+    Ok(tokens)
+}
+
+fn is_word_char(char: char) -> bool {
+    char.is_ascii_alphanumeric() || char == '_'
+}
+
+fn read_string(
+    chars: &[char],
+    start: usize,
+    line_number: usize,
+) -> Result<(String, usize), AssemblyError> {
+    let mut string = String::new();
+    let mut index = start + 1;
+    while index < chars.len() {
+        match chars[index] {
+            STRING_DELIMITER => return Ok((string, index + 1)),
+            '\\' => {
+                let escaped = chars.get(index + 1).copied().ok_or_else(|| {
+                    AssemblyError::new(
+                        line_number,
+                        start + 1,
+                        AssemblyErrorKind::UnterminatedString,
+                    )
+                })?;
+                string.push(match escaped {
+                    'n' => '\n',
+                    't' => '\t',
+                    'r' => '\r',
+                    'e' => '\x1b',
+                    '0' => '\0',
+                    '\\' => '\\',
+                    '"' => '"',
+                    _ => {
+                        return Err(AssemblyError::new(
+                            line_number,
+                            index + 1,
+                            AssemblyErrorKind::InvalidEscape(escaped),
+                        ))
+                    }
+                });
+                index += 2;
+            }
+            char => {
+                string.push(char);
+                index += 1;
+            }
+        }
+    }
+    Err(AssemblyError::new(
+        line_number,
+        start + 1,
+        AssemblyErrorKind::UnterminatedString,
+    ))
+}
+
+/// Words that look numeric but don't parse (like `#12a`) are rejected, while
+/// `x`-prefixed words that aren't valid hex (like `xor_mask`) are labels.
+fn classify_word(word: &str) -> Option<TokenKind> {
+    if let Some(register) = parse_register(word) {
+        return Some(TokenKind::Register(register));
+    }
+    if word.starts_with(['#', '-']) || word.starts_with(|char: char| char.is_ascii_digit()) {
+        return parse_number(word).map(TokenKind::Number);
+    }
+    if let Some(number) = parse_number(word) {
+        return Some(TokenKind::Number(number));
+    }
+    if word.contains('-') {
+        return None;
+    }
+    Some(TokenKind::Identifier(word.to_string()))
+}
+
+/// Accepts the number formats of LC-3 assembly: `#-12`, `-12`, `12` and `x3000`.
+pub fn parse_number(word: &str) -> Option<i32> {
+    if let Some(decimal) = word.strip_prefix('#') {
+        return parse_signed(decimal, 10);
+    }
+    if let Some(hexadecimal) = word.strip_prefix(['x', 'X']) {
+        return parse_signed(hexadecimal, 16);
+    }
+    parse_signed(word, 10)
+}
+
+fn parse_register(word: &str) -> Option<u16> {
+    match word.strip_prefix(['r', 'R'])?.as_bytes() {
+        [digit @ b'0'..=b'7'] => Some((digit - b'0') as u16),
+        _ => None,
+    }
+}
+
+fn parse_signed(digits: &str, radix: u32) -> Option<i32> {
+    let (is_negative, digits) = match digits.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, digits),
+    };
+    if digits.is_empty() || digits.starts_with(['+', '-']) {
+        return None;
+    }
+    let value = i32::from_str_radix(digits, radix).ok()?;
+    Some(if is_negative { -value } else { value })
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::{tokenize_line, Token, TokenKind};
+    use crate::assembler::error::AssemblyErrorKind;
+
+    #[test]
+    fn can_tokenize_an_instruction_with_a_comment() {
+        let tokens = tokenize_line("LOOP ADD R1, R1, #-1 ; count down", 1).unwrap();
+        let kinds: Vec<TokenKind> = tokens.into_iter().map(|token| token.kind).collect();
+
+        assert_eq!(
+            kinds,
+            vec![
+                TokenKind::Identifier("LOOP".to_string()),
+                TokenKind::Identifier("ADD".to_string()),
+                TokenKind::Register(1),
+                TokenKind::Comma,
+                TokenKind::Register(1),
+                TokenKind::Comma,
+                TokenKind::Number(-1),
+            ]
+        );
+    }
+
+    #[test]
+    fn hex_numbers_and_hex_looking_labels_are_told_apart() {
+        let tokens = tokenize_line(".FILL x3000 xyz", 1).unwrap();
+
+        assert_eq!(tokens[1].kind, TokenKind::Number(0x3000));
+        assert_eq!(tokens[2].kind, TokenKind::Identifier("xyz".to_string()));
+    }
+
+    #[test]
+    fn strings_keep_escapes_and_semicolons() {
+        let tokens = tokenize_line(".STRINGZ \"a;b\\n\"", 1).unwrap();
+
+        assert_eq!(
+            tokens[1],
+            Token {
+                kind: TokenKind::String("a;b\n".to_string()),
+                column: 10,
+            }
+        );
+    }
+
+    #[test]
+    fn errors_report_the_column() {
+        let error = tokenize_line("  ADD R1, R1, @", 4).unwrap_err();
+
+        assert_eq!(error.line, 4);
+        assert_eq!(error.column, 15);
+        assert_eq!(error.kind, AssemblyErrorKind::UnexpectedCharacter('@'));
+    }
+}
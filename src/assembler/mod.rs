@@ -0,0 +1,5 @@
+pub mod disassembler;
+pub mod error;
+pub mod lc3_assembler;
+pub mod lexer;
+pub mod symbol_table;
@@ -0,0 +1,183 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    io::Error,
+    path::Path,
+};
+
+use super::lexer::parse_number;
+
+pub const SYMBOL_FILE_EXTENSION: &str = "sym";
+/// Addresses further than this from the closest label before them are shown as plain addresses.
+const MAXIMUM_LABEL_OFFSET: u16 = 0x100;
+const SYMBOL_FILE_HEADER: &str = "// Symbol table\n// Scope level 0:\n//\tSymbol Name       Page Address\n//\t----------------  ------------\n";
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SymbolTable {
+    addresses: HashMap<String, u16>,
+    labels: BTreeMap<u16, String>,
+}
+
+impl SymbolTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns false if the label was already defined.
+    pub fn insert(&mut self, label: &str, address: u16) -> bool {
+        if self.addresses.contains_key(label) {
+            return false;
+        }
+        self.addresses.insert(label.to_string(), address);
+        self.labels
+            .entry(address)
+            .or_insert_with(|| label.to_string());
+        true
+    }
+
+    /// Labels already defined keep their address.
+    pub fn merge(&mut self, other: SymbolTable) {
+        let mut symbols: Vec<(String, u16)> = other.addresses.into_iter().collect();
+        symbols.sort_by_key(|(label, address)| (*address, label.clone()));
+        for (label, address) in symbols {
+            self.insert(&label, address);
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.addresses.is_empty()
+    }
+
+    pub fn address_of(&self, label: &str) -> Option<u16> {
+        self.addresses.get(label).copied()
+    }
+
+    pub fn label_at(&self, address: u16) -> Option<&str> {
+        self.labels.get(&address).map(String::as_str)
+    }
+
+    /// `LABEL` or `LABEL+offset` for the closest label at or before the address.
+    pub fn describe(&self, address: u16) -> Option<String> {
+        let (label_address, label) = self.labels.range(..=address).next_back()?;
+        match address - label_address {
+            0 => Some(label.clone()),
+            offset if offset <= MAXIMUM_LABEL_OFFSET => Some(format!("{label}+{offset}")),
+            _ => None,
+        }
+    }
+
+    /// `x3004` or `x3004 <LOOP+2>` when there is a label nearby.
+    pub fn format_address(&self, address: u16) -> String {
+        match self.describe(address) {
+            Some(description) => format!("x{address:04X} <{description}>"),
+            None => format!("x{address:04X}"),
+        }
+    }
+
+    /// Parses a number (`x3000`, `#12`, `12`) or a label with an optional offset (`LOOP+2`, `LOOP-1`).
+    pub fn parse_address(&self, text: &str) -> Option<u16> {
+        if let Some(number) = parse_number(text) {
+            return u16::try_from(number)
+                .ok()
+                .or_else(|| i16::try_from(number).ok().map(|number| number as u16));
+        }
+        let (label, offset) = match text.find(['+', '-']) {
+            Some(index) => {
+                let offset = parse_number(&text[index + 1..])?;
+                let offset = if text[index..].starts_with('-') {
+                    -offset
+                } else {
+                    offset
+                };
+                (&text[..index], offset)
+            }
+            None => (text, 0),
+        };
+        let address = self.address_of(label)? as i32 + offset;
+        u16::try_from(address).ok()
+    }
+
+    /// Same layout as the `.sym` files written by `lc3as`.
+    pub fn to_symbol_file(&self) -> String {
+        let mut symbol_file = SYMBOL_FILE_HEADER.to_string();
+        let mut symbols: Vec<(&String, &u16)> = self.addresses.iter().collect();
+        symbols.sort_by_key(|(label, address)| (**address, label.as_str()));
+        for (label, address) in symbols {
+            symbol_file += &format!("//\t{label:<16}  {address:04X}\n");
+        }
+        symbol_file + "\n"
+    }
+
+    pub fn parse_symbol_file(symbol_file: &str) -> Self {
+        let mut symbol_table = Self::new();
+        for line in symbol_file.lines() {
+            let line = line.trim_start_matches('/');
+            if let [label, address] = line.split_whitespace().collect::<Vec<&str>>()[..] {
+                if let Ok(address) = u16::from_str_radix(address, 16) {
+                    symbol_table.insert(label, address);
+                }
+            }
+        }
+        symbol_table
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Error> {
+        Ok(Self::parse_symbol_file(&fs::read_to_string(path)?))
+    }
+
+    /// Loads the `.sym` file next to an object file, an empty table if there is none.
+    pub fn load_for_object_file(object_path: impl AsRef<Path>) -> Result<Self, Error> {
+        let symbol_path = object_path.as_ref().with_extension(SYMBOL_FILE_EXTENSION);
+        if !symbol_path.exists() {
+            return Ok(Self::new());
+        }
+        Self::load(symbol_path)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        fs::write(path, self.to_symbol_file())
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::SymbolTable;
+
+    fn symbol_table() -> SymbolTable {
+        let mut symbol_table = SymbolTable::new();
+        symbol_table.insert("LOOP", 0x3000);
+        symbol_table.insert("DATA", 0x3004);
+        symbol_table
+    }
+
+    #[test]
+    fn addresses_are_described_relative_to_the_closest_label() {
+        let symbol_table = symbol_table();
+
+        assert_eq!(symbol_table.describe(0x3000).unwrap(), "LOOP");
+        assert_eq!(symbol_table.describe(0x3002).unwrap(), "LOOP+2");
+        assert_eq!(symbol_table.describe(0x3005).unwrap(), "DATA+1");
+        assert_eq!(symbol_table.describe(0x2FFF), None);
+        assert_eq!(symbol_table.format_address(0x3001), "x3001 <LOOP+1>");
+    }
+
+    #[test]
+    fn can_parse_labels_with_offsets_and_numbers() {
+        let symbol_table = symbol_table();
+
+        assert_eq!(symbol_table.parse_address("DATA"), Some(0x3004));
+        assert_eq!(symbol_table.parse_address("LOOP+3"), Some(0x3003));
+        assert_eq!(symbol_table.parse_address("DATA-x2"), Some(0x3002));
+        assert_eq!(symbol_table.parse_address("x4000"), Some(0x4000));
+        assert_eq!(symbol_table.parse_address("MISSING"), None);
+    }
+
+    #[test]
+    fn symbol_file_round_trips_in_the_lc3as_layout() {
+        let symbol_table = symbol_table();
+        let symbol_file = symbol_table.to_symbol_file();
+
+        assert!(symbol_file.contains("//\tLOOP              3000\n"));
+        assert_eq!(SymbolTable::parse_symbol_file(&symbol_file), symbol_table);
+    }
+}
@@ -4,13 +4,30 @@ use std::{
     os::unix::net::UnixDatagram,
 };
 
-use lc3_vm::constants::{CLIENT_PATH, SERVER_PATH, STREAM_DATA_SEPARATOR};
+use lc3_vm::constants::{CLIENT_PATH, DATAGRAM_BUFFER_SIZE, SERVER_PATH, STREAM_DATA_SEPARATOR};
 
 fn print_instructions() {
     println!("Instructions: ");
     println!("<n> to execute one instruction");
+    println!(
+        "<next> to execute one instruction, running subroutine calls and traps until they return"
+    );
+    println!("<finish> to run until the current subroutine returns");
+    println!("<bt> to show the subroutine calls that haven't returned");
     println!("<a number> to execute that number of instructions (recommended)");
     println!("<r> to print the state of the registers of the program");
+    println!("<b ADDRESS> to set a breakpoint, the address can be x3000 or a label like LOOP+2");
+    println!("<breakpoints> to list the breakpoints");
+    println!("<enable ADDRESS>, <disable ADDRESS> and <delete ADDRESS> to change a breakpoint");
+    println!("<c> to continue until a breakpoint, a watchpoint, HALT or an error");
+    println!(
+        "<watch START [END]> to stop on writes to a range, <rwatch> on reads, <awatch> on both"
+    );
+    println!("<watchpoints> to list the watchpoints and <unwatch START [END]> to delete them");
+    println!("<x ADDRESS [COUNT]> to show memory as hex, decimal, ASCII and instructions");
+    println!("<set mem ADDRESS VALUE> and <set reg R3 VALUE> to change memory or a register");
+    println!("<fill START END VALUE> to write a value on a range of memory");
+    println!("<load FILE [ADDRESS]> to write a program image at its origin or at ADDRESS");
     println!("remember to use the program you are debugging if it needs user input");
 }
 
@@ -21,7 +38,7 @@ fn main() -> Result<(), Error> {
     for line in stdin().lock().lines() {
         let line = line?;
         socket.send_to(line.as_bytes(), SERVER_PATH)?;
-        let mut buffer = [0; 1024];
+        let mut buffer = vec![0; DATAGRAM_BUFFER_SIZE];
         match socket.recv(&mut buffer) {
             Ok(size) => {
                 let response = String::from_utf8_lossy(&buffer[..size]);
@@ -1,4 +1,7 @@
 pub const SERVER_PATH: &str = "/tmp/server_debugger1.socket";
 pub const CLIENT_PATH: &str = "/tmp/client_debugger1.socket";
 pub const STREAM_DATA_SEPARATOR: &str = "::";
+pub const DATAGRAM_BUFFER_SIZE: usize = 1 << 16;
 pub const DEFAULT_PROGRAM_COUNTER_START: u16 = 0x3000;
+pub const DEFAULT_SUPERVISOR_STACK_POINTER: u16 = 0x3000;
+pub const DEFAULT_USER_STACK_POINTER: u16 = 0xFE00;
@@ -0,0 +1,82 @@
+use std::collections::BTreeMap;
+
+/// Breakpoints by address, each one enabled or disabled.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Breakpoints {
+    breakpoints: BTreeMap<u16, bool>,
+}
+
+impl Breakpoints {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns false if there was already a breakpoint at the address, which is enabled again.
+    pub fn set(&mut self, address: u16) -> bool {
+        self.breakpoints.insert(address, true).is_none()
+    }
+
+    pub fn delete(&mut self, address: u16) -> bool {
+        self.breakpoints.remove(&address).is_some()
+    }
+
+    /// Returns false if there is no breakpoint at the address.
+    pub fn set_enabled(&mut self, address: u16, enabled: bool) -> bool {
+        match self.breakpoints.get_mut(&address) {
+            Some(is_enabled) => {
+                *is_enabled = enabled;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn is_enabled_at(&self, address: u16) -> bool {
+        self.breakpoints.get(&address).copied().unwrap_or(false)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.breakpoints.is_empty()
+    }
+
+    /// Sorted by address.
+    pub fn iter(&self) -> impl Iterator<Item = (u16, bool)> + '_ {
+        self.breakpoints
+            .iter()
+            .map(|(address, enabled)| (*address, *enabled))
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::Breakpoints;
+
+    #[test]
+    fn disabled_breakpoints_are_kept_but_do_not_stop() {
+        let mut breakpoints = Breakpoints::new();
+        breakpoints.set(0x3004);
+        breakpoints.set(0x3000);
+
+        assert!(breakpoints.set_enabled(0x3004, false));
+        assert!(!breakpoints.set_enabled(0x3001, false));
+        assert!(!breakpoints.is_enabled_at(0x3004));
+        assert!(breakpoints.is_enabled_at(0x3000));
+        assert_eq!(
+            breakpoints.iter().collect::<Vec<_>>(),
+            vec![(0x3000, true), (0x3004, false)]
+        );
+    }
+
+    #[test]
+    fn setting_a_breakpoint_twice_enables_it_again() {
+        let mut breakpoints = Breakpoints::new();
+
+        assert!(breakpoints.set(0x3000));
+        breakpoints.set_enabled(0x3000, false);
+        assert!(!breakpoints.set(0x3000));
+        assert!(breakpoints.is_enabled_at(0x3000));
+        assert!(breakpoints.delete(0x3000));
+        assert!(!breakpoints.delete(0x3000));
+        assert!(breakpoints.is_empty());
+    }
+}
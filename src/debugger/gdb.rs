@@ -0,0 +1,701 @@
+use std::{
+    io::{Error, ErrorKind, Read, Write},
+    net::TcpStream,
+};
+
+use crate::virtual_machine::{
+    error::VmError, lc3_virtual_machine::LC3VirtualMachine, register::Register,
+};
+
+use super::{
+    target::{DebugTarget, Stop},
+    watchpoint::{WatchKind, Watchpoint},
+};
+
+const PACKET_START: u8 = b'$';
+const CHECKSUM_START: u8 = b'#';
+const ACKNOWLEDGE: u8 = b'+';
+const REQUEST_RETRANSMISSION: u8 = b'-';
+const INTERRUPT: u8 = 0x03;
+/// Advertised as `PacketSize`, longer incoming packets are refused.
+const MAXIMUM_PACKET_SIZE: usize = 0x1000;
+/// Every byte read takes two hex digits in the reply.
+const MAXIMUM_MEMORY_READ: usize = MAXIMUM_PACKET_SIZE / 2;
+const INSTRUCTIONS_BETWEEN_INTERRUPT_CHECKS: u64 = 1024;
+/// R0-R7, PC and PSR, in that order.
+const AMOUNT_OF_GDB_REGISTERS: u16 = 10;
+const PROGRAM_COUNTER_NUMBER: u16 = 8;
+const PROCESSOR_STATUS_NUMBER: u16 = 9;
+
+const SIGINT: u8 = 2;
+const SIGILL: u8 = 4;
+const SIGTRAP: u8 = 5;
+const SIGABRT: u8 = 6;
+const SIGSEGV: u8 = 11;
+
+/// What the client does at the end of a session.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GdbExit {
+    Detached,
+    Killed,
+    Disconnected,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GdbResponse {
+    Reply(String),
+    Detach,
+    Kill,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Incoming {
+    Packet(String),
+    Interrupt,
+    TooLong,
+}
+
+/// Serves the GDB remote serial protocol for one machine.
+/// Addresses are LC-3 word addresses, lengths count bytes and every word is two bytes,
+/// most significant first like in object files.
+pub struct GdbStub {
+    target: DebugTarget,
+}
+
+impl GdbStub {
+    pub fn new(virtual_machine: LC3VirtualMachine) -> Self {
+        Self {
+            target: DebugTarget::new(virtual_machine),
+        }
+    }
+
+    pub fn virtual_machine(&self) -> &LC3VirtualMachine {
+        &self.target.virtual_machine
+    }
+
+    pub fn into_virtual_machine(self) -> LC3VirtualMachine {
+        self.target.into_virtual_machine()
+    }
+
+    pub fn serve(&mut self, mut stream: TcpStream) -> Result<GdbExit, Error> {
+        loop {
+            let packet = match read_packet(&mut stream)? {
+                Some(Incoming::Packet(packet)) => packet,
+                Some(Incoming::Interrupt) => continue,
+                Some(Incoming::TooLong) => {
+                    write_packet(&mut stream, &error_reply())?;
+                    continue;
+                }
+                None => return Ok(GdbExit::Disconnected),
+            };
+            let mut interrupt_poller = InterruptPoller::new(&stream);
+            let response = self.handle_packet(&packet, &mut || interrupt_poller.is_interrupted());
+            if interrupt_poller.finish()? {
+                return Ok(GdbExit::Disconnected);
+            }
+            match response {
+                GdbResponse::Reply(reply) => write_packet(&mut stream, &reply)?,
+                GdbResponse::Detach => {
+                    write_packet(&mut stream, "OK")?;
+                    return Ok(GdbExit::Detached);
+                }
+                GdbResponse::Kill => return Ok(GdbExit::Killed),
+            }
+        }
+    }
+
+    /// `is_interrupted` is polled while the program runs after `c`.
+    pub fn handle_packet(
+        &mut self,
+        packet: &str,
+        is_interrupted: &mut dyn FnMut() -> bool,
+    ) -> GdbResponse {
+        // packets that don't start with an ASCII command fall through to the empty reply
+        let reply = match packet.split_at_checked(1).unwrap_or(("", packet)) {
+            ("?", _) => stop_reply(SIGTRAP),
+            ("g", "") => self.read_registers(),
+            ("G", values) => self.write_registers(values),
+            ("p", number) => self.read_register(number),
+            ("P", assignment) => self.write_register(assignment),
+            ("m", arguments) => self.read_memory(arguments),
+            ("M", arguments) => self.write_memory(arguments),
+            ("s", address) => self.resume(address, |stub| stub.step()),
+            ("c", address) => self.resume(address, |stub| stub.continue_execution(is_interrupted)),
+            ("Z", arguments) => self.update_stop_point(arguments, true),
+            ("z", arguments) => self.update_stop_point(arguments, false),
+            ("H", _) => "OK".to_string(),
+            ("k", _) => return GdbResponse::Kill,
+            ("D", _) => return GdbResponse::Detach,
+            _ if packet.starts_with("qSupported") => {
+                format!("PacketSize={MAXIMUM_PACKET_SIZE:x};swbreak+")
+            }
+            _ if packet == "qAttached" => "1".to_string(),
+            _ => String::new(),
+        };
+        GdbResponse::Reply(reply)
+    }
+
+    fn read_registers(&self) -> String {
+        (0..AMOUNT_OF_GDB_REGISTERS)
+            .filter_map(|number| self.register_value(number))
+            .map(|value| format!("{value:04x}"))
+            .collect()
+    }
+
+    fn write_registers(&mut self, values: &str) -> String {
+        if values.len() != AMOUNT_OF_GDB_REGISTERS as usize * 4 {
+            return error_reply();
+        }
+        let Some(values) = parse_words(values) else {
+            return error_reply();
+        };
+        for (number, value) in values.into_iter().enumerate() {
+            self.update_register_value(number as u16, value);
+        }
+        "OK".to_string()
+    }
+
+    fn read_register(&self, number: &str) -> String {
+        u16::from_str_radix(number, 16)
+            .ok()
+            .and_then(|number| self.register_value(number))
+            .map(|value| format!("{value:04x}"))
+            .unwrap_or_else(error_reply)
+    }
+
+    fn write_register(&mut self, assignment: &str) -> String {
+        let Some((number, value)) = assignment.split_once('=') else {
+            return error_reply();
+        };
+        let number = u16::from_str_radix(number, 16).ok();
+        let value = parse_words(value).filter(|values| values.len() == 1);
+        match (number, value) {
+            (Some(number @ 0..AMOUNT_OF_GDB_REGISTERS), Some(value)) => {
+                self.update_register_value(number, value[0]);
+                "OK".to_string()
+            }
+            _ => error_reply(),
+        }
+    }
+
+    fn register_value(&self, number: u16) -> Option<u16> {
+        match number {
+            PROGRAM_COUNTER_NUMBER => Some(
+                self.target
+                    .virtual_machine
+                    .read_register(Register::ProgramCounter),
+            ),
+            PROCESSOR_STATUS_NUMBER => Some(self.target.virtual_machine.processor_status()),
+            _ => {
+                let register = Register::try_from(number).ok().filter(|_| number < 8)?;
+                Some(self.target.virtual_machine.read_register(register))
+            }
+        }
+    }
+
+    fn update_register_value(&mut self, number: u16, value: u16) {
+        match number {
+            PROGRAM_COUNTER_NUMBER => self
+                .target
+                .virtual_machine
+                .update_register(Register::ProgramCounter, value),
+            PROCESSOR_STATUS_NUMBER => self.target.virtual_machine.update_processor_status(value),
+            _ => {
+                if let Ok(register) = Register::try_from(number) {
+                    self.target.virtual_machine.update_register(register, value);
+                }
+            }
+        }
+    }
+
+    /// Device registers can't be read without side effects, so reading them is an error.
+    fn read_memory(&self, arguments: &str) -> String {
+        let Some((address, length)) = parse_address_and_length(arguments) else {
+            return error_reply();
+        };
+        if length > MAXIMUM_MEMORY_READ {
+            return error_reply();
+        }
+        let mut bytes = String::new();
+        for offset in 0..length.div_ceil(2) {
+            let Some(word) = self
+                .target
+                .virtual_machine
+                .peek_memory(address.wrapping_add(offset as u16))
+            else {
+                return error_reply();
+            };
+            bytes += &format!("{word:04x}");
+        }
+        bytes.truncate(length * 2);
+        bytes
+    }
+
+    fn write_memory(&mut self, arguments: &str) -> String {
+        let Some((arguments, data)) = arguments.split_once(':') else {
+            return error_reply();
+        };
+        let Some((address, length)) = parse_address_and_length(arguments) else {
+            return error_reply();
+        };
+        let words = parse_words(data).filter(|_| length % 2 == 0 && data.len() == length * 2);
+        let Some(words) = words else {
+            return error_reply();
+        };
+        for (offset, word) in words.into_iter().enumerate() {
+            let memory_address = address.wrapping_add(offset as u16);
+            if self
+                .target
+                .virtual_machine
+                .memory_write(memory_address, word)
+                .is_err()
+            {
+                return error_reply();
+            }
+        }
+        "OK".to_string()
+    }
+
+    /// `Z0`/`z0` are breakpoints, `Z2` to `Z4` write, read and access watchpoints.
+    fn update_stop_point(&mut self, arguments: &str, insert: bool) -> String {
+        let mut arguments = arguments.split(',');
+        let (Some(kind), Some(address), Some(length), None) = (
+            arguments.next(),
+            arguments.next(),
+            arguments.next(),
+            arguments.next(),
+        ) else {
+            return error_reply();
+        };
+        let (Ok(address), Ok(length)) = (
+            u16::from_str_radix(address, 16),
+            u16::from_str_radix(length, 16),
+        ) else {
+            return error_reply();
+        };
+        let watch_kind = match kind {
+            "0" => {
+                if insert {
+                    self.target.breakpoints.set(address);
+                } else {
+                    self.target.breakpoints.delete(address);
+                }
+                return "OK".to_string();
+            }
+            "2" => WatchKind::Write,
+            "3" => WatchKind::Read,
+            "4" => WatchKind::Access,
+            _ => return String::new(),
+        };
+        let watchpoint = Watchpoint {
+            kind: watch_kind,
+            start: address,
+            end: address.wrapping_add(length.div_ceil(2).max(1) - 1),
+        };
+        if insert {
+            self.target.watchpoints.add(watchpoint);
+        } else {
+            self.target.watchpoints.remove(&watchpoint);
+        }
+        "OK".to_string()
+    }
+
+    fn resume(&mut self, address: &str, run: impl FnOnce(&mut Self) -> String) -> String {
+        if !address.is_empty() {
+            let Ok(address) = u16::from_str_radix(address, 16) else {
+                return error_reply();
+            };
+            self.target
+                .virtual_machine
+                .update_register(Register::ProgramCounter, address);
+        }
+        run(self)
+    }
+
+    fn step(&mut self) -> String {
+        self.target
+            .step()
+            .map(stop_point_reply)
+            .unwrap_or_else(|| stop_reply(SIGTRAP))
+    }
+
+    fn continue_execution(&mut self, is_interrupted: &mut dyn FnMut() -> bool) -> String {
+        let mut executed_instructions: u64 = 0;
+        let stop = self.target.run_until(|_| {
+            executed_instructions += 1;
+            executed_instructions.is_multiple_of(INSTRUCTIONS_BETWEEN_INTERRUPT_CHECKS)
+                && is_interrupted()
+        });
+        stop.map(stop_point_reply)
+            .unwrap_or_else(|| stop_reply(SIGINT))
+    }
+}
+
+/// Watchpoints report the first address that triggered one.
+fn stop_point_reply(stop: Stop) -> String {
+    match stop {
+        Stop::Halted => "W00".to_string(),
+        Stop::Failed(error) => stop_reply(signal_for(&error)),
+        Stop::Breakpoint(_) => format!("T{SIGTRAP:02x}swbreak:;"),
+        Stop::Watchpoints { hits, .. } => {
+            let hit = hits[0];
+            let reason = match hit.watchpoint.kind {
+                WatchKind::Write => "watch",
+                WatchKind::Read => "rwatch",
+                WatchKind::Access => "awatch",
+            };
+            format!("T{SIGTRAP:02x}{reason}:{:04x};", hit.access.address())
+        }
+    }
+}
+
+fn signal_for(error: &VmError) -> u8 {
+    match error {
+        VmError::IllegalOpcode(_) | VmError::UnknownTrap(_) => SIGILL,
+        VmError::PrivilegeModeViolation | VmError::AccessControlViolation(_) => SIGSEGV,
+        _ => SIGABRT,
+    }
+}
+
+fn stop_reply(signal: u8) -> String {
+    format!("S{signal:02x}")
+}
+
+fn error_reply() -> String {
+    "E01".to_string()
+}
+
+fn parse_address_and_length(arguments: &str) -> Option<(u16, usize)> {
+    let (address, length) = arguments.split_once(',')?;
+    let address = u16::from_str_radix(address, 16).ok()?;
+    let length = usize::from_str_radix(length, 16).ok()?;
+    Some((address, length))
+}
+
+/// Four hex digits per word.
+fn parse_words(hex: &str) -> Option<Vec<u16>> {
+    if !hex.len().is_multiple_of(4) || !hex.is_ascii() {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(4)
+        .map(|index| u16::from_str_radix(&hex[index..index + 4], 16).ok())
+        .collect()
+}
+
+fn checksum(data: &str) -> u8 {
+    data.bytes()
+        .fold(0, |checksum, byte| checksum.wrapping_add(byte))
+}
+
+pub fn encode_packet(data: &str) -> String {
+    format!("${data}#{:02x}", checksum(data))
+}
+
+fn write_packet(stream: &mut impl Write, data: &str) -> Result<(), Error> {
+    stream.write_all(encode_packet(data).as_bytes())?;
+    stream.flush()
+}
+
+/// Acknowledges every packet, asking again for the ones with a bad checksum.
+/// None when the client has closed the connection.
+pub fn read_packet(stream: &mut (impl Read + Write)) -> Result<Option<Incoming>, Error> {
+    loop {
+        let Some(byte) = read_byte(stream)? else {
+            return Ok(None);
+        };
+        match byte {
+            INTERRUPT => return Ok(Some(Incoming::Interrupt)),
+            PACKET_START => {}
+            _ => continue,
+        }
+        let mut data = Vec::new();
+        let mut is_too_long = false;
+        let mut data_checksum: u8 = 0;
+        loop {
+            match read_byte(stream)? {
+                Some(CHECKSUM_START) => break,
+                Some(byte) => {
+                    data_checksum = data_checksum.wrapping_add(byte);
+                    if data.len() < MAXIMUM_PACKET_SIZE {
+                        data.push(byte);
+                    } else {
+                        is_too_long = true;
+                    }
+                }
+                None => return Ok(None),
+            }
+        }
+        let mut expected_checksum = [0; 2];
+        stream.read_exact(&mut expected_checksum)?;
+        let expected_checksum = std::str::from_utf8(&expected_checksum)
+            .ok()
+            .and_then(|checksum| u8::from_str_radix(checksum, 16).ok());
+        if expected_checksum != Some(data_checksum) {
+            stream.write_all(&[REQUEST_RETRANSMISSION])?;
+            continue;
+        }
+        stream.write_all(&[ACKNOWLEDGE])?;
+        if is_too_long {
+            return Ok(Some(Incoming::TooLong));
+        }
+        return Ok(Some(Incoming::Packet(
+            String::from_utf8_lossy(&data).into_owned(),
+        )));
+    }
+}
+
+fn read_byte(stream: &mut impl Read) -> Result<Option<u8>, Error> {
+    let mut byte = [0];
+    match stream.read(&mut byte)? {
+        0 => Ok(None),
+        _ => Ok(Some(byte[0])),
+    }
+}
+
+/// Looks for Ctrl-C while the program runs. The socket is non-blocking from the first poll
+/// until `finish`, and bytes other than the interrupt are left for `read_packet`.
+struct InterruptPoller<'a> {
+    stream: &'a TcpStream,
+    is_nonblocking: bool,
+    is_disconnected: bool,
+}
+
+impl<'a> InterruptPoller<'a> {
+    fn new(stream: &'a TcpStream) -> Self {
+        Self {
+            stream,
+            is_nonblocking: false,
+            is_disconnected: false,
+        }
+    }
+
+    /// A closed connection also stops the program, nobody is left to interrupt it.
+    fn is_interrupted(&mut self) -> bool {
+        self.poll().unwrap_or_else(|_| {
+            self.is_disconnected = true;
+            true
+        })
+    }
+
+    fn poll(&mut self) -> Result<bool, Error> {
+        if !self.is_nonblocking {
+            self.stream.set_nonblocking(true)?;
+            self.is_nonblocking = true;
+        }
+        let mut byte = [0];
+        match self.stream.peek(&mut byte) {
+            Ok(0) => Err(Error::new(
+                ErrorKind::UnexpectedEof,
+                "the client disconnected",
+            )),
+            Ok(_) if byte[0] == INTERRUPT => {
+                self.stream.read_exact(&mut byte)?;
+                Ok(true)
+            }
+            Ok(_) => Ok(false),
+            Err(error) if error.kind() == ErrorKind::WouldBlock => Ok(false),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Restores the blocking mode, returning true if the client disconnected.
+    fn finish(self) -> Result<bool, Error> {
+        if self.is_nonblocking && !self.is_disconnected {
+            self.stream.set_nonblocking(false)?;
+        }
+        Ok(self.is_disconnected)
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use std::{
+        io::{Cursor, Read, Write},
+        net::{TcpListener, TcpStream},
+        thread::{self, JoinHandle},
+    };
+
+    use super::{
+        encode_packet, read_packet, GdbExit, GdbResponse, GdbStub, Incoming, MAXIMUM_PACKET_SIZE,
+    };
+    use crate::debugger::target::test::assembled_program;
+
+    const STORE_IN_TABLE: &str = "
+            .ORIG x3000
+            LEA R1, TABLE
+            ADD R0, R0, #7
+    STORE   STR R0, R1, #1
+            HALT
+    TABLE   .BLKW 2
+            .END
+    ";
+
+    fn gdb_stub(source: &str) -> GdbStub {
+        GdbStub::new(assembled_program(source).0)
+    }
+
+    fn reply(gdb_stub: &mut GdbStub, packet: &str) -> String {
+        match gdb_stub.handle_packet(packet, &mut || false) {
+            GdbResponse::Reply(reply) => reply,
+            response => panic!("expected a reply, got {response:?}"),
+        }
+    }
+
+    #[test]
+    fn packets_carry_their_checksum() {
+        assert_eq!(encode_packet("OK"), "$OK#9a");
+        assert_eq!(encode_packet(""), "$#00");
+    }
+
+    struct ScriptedStream {
+        input: Cursor<Vec<u8>>,
+        output: Vec<u8>,
+    }
+
+    impl Read for ScriptedStream {
+        fn read(&mut self, buffer: &mut [u8]) -> std::io::Result<usize> {
+            self.input.read(buffer)
+        }
+    }
+
+    impl Write for ScriptedStream {
+        fn write(&mut self, buffer: &[u8]) -> std::io::Result<usize> {
+            self.output.write(buffer)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn packets_longer_than_the_packet_size_are_refused() {
+        let long_packet = encode_packet(&"0".repeat(MAXIMUM_PACKET_SIZE + 1));
+        let mut stream = ScriptedStream {
+            input: Cursor::new([long_packet.as_bytes(), b"$#AA$g#67"].concat()),
+            output: Vec::new(),
+        };
+
+        assert_eq!(read_packet(&mut stream).unwrap(), Some(Incoming::TooLong));
+        assert_eq!(
+            read_packet(&mut stream).unwrap(),
+            Some(Incoming::Packet("g".to_string()))
+        );
+        assert_eq!(stream.output, b"+-+");
+        let mut gdb_stub = gdb_stub(STORE_IN_TABLE);
+        assert_eq!(reply(&mut gdb_stub, "m0,ffffffff"), "E01");
+        assert_eq!(reply(&mut gdb_stub, "m0,800").len(), MAXIMUM_PACKET_SIZE);
+    }
+
+    #[test]
+    fn unknown_and_non_ascii_packets_get_an_empty_reply() {
+        let mut gdb_stub = gdb_stub(STORE_IN_TABLE);
+
+        assert_eq!(reply(&mut gdb_stub, "é"), "");
+        assert_eq!(reply(&mut gdb_stub, "éc"), "");
+        assert_eq!(reply(&mut gdb_stub, ""), "");
+        assert_eq!(reply(&mut gdb_stub, "vMustReplyEmpty"), "");
+    }
+
+    #[test]
+    fn registers_and_memory_can_be_read_and_written() {
+        let mut gdb_stub = gdb_stub(STORE_IN_TABLE);
+
+        assert_eq!(
+            reply(&mut gdb_stub, "g"),
+            format!("{}30000000", "0000".repeat(8))
+        );
+        assert_eq!(reply(&mut gdb_stub, "P3=abcd"), "OK");
+        assert_eq!(reply(&mut gdb_stub, "p3"), "abcd");
+        assert_eq!(reply(&mut gdb_stub, "pa"), "E01");
+        assert_eq!(reply(&mut gdb_stub, "m3000,4"), "e2031027");
+        assert_eq!(reply(&mut gdb_stub, "m3000,3"), "e20310");
+        assert_eq!(reply(&mut gdb_stub, "M4000,4:12345678"), "OK");
+        assert_eq!(reply(&mut gdb_stub, "m4000,4"), "12345678");
+        assert_eq!(reply(&mut gdb_stub, "mfe00,2"), "E01");
+    }
+
+    #[test]
+    fn stepping_and_stop_points_report_why_the_program_stopped() {
+        let mut gdb_stub = gdb_stub(STORE_IN_TABLE);
+
+        assert_eq!(reply(&mut gdb_stub, "s"), "S05");
+        assert_eq!(reply(&mut gdb_stub, "Z0,3002,2"), "OK");
+        assert_eq!(reply(&mut gdb_stub, "c"), "T05swbreak:;");
+        assert_eq!(reply(&mut gdb_stub, "z0,3002,2"), "OK");
+        assert_eq!(reply(&mut gdb_stub, "Z2,3004,4"), "OK");
+        assert_eq!(reply(&mut gdb_stub, "c"), "T05watch:3005;");
+        assert_eq!(reply(&mut gdb_stub, "c"), "W00");
+        assert_eq!(
+            gdb_stub.handle_packet("k", &mut || false),
+            GdbResponse::Kill
+        );
+    }
+
+    fn serve_in_background(source: &'static str) -> (JoinHandle<GdbExit>, TcpStream) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            gdb_stub(source).serve(stream).unwrap()
+        });
+        (server, TcpStream::connect(("127.0.0.1", port)).unwrap())
+    }
+
+    fn send(client: &mut TcpStream, packet: &str) {
+        client.write_all(encode_packet(packet).as_bytes()).unwrap();
+        let mut acknowledge = [0];
+        client.read_exact(&mut acknowledge).unwrap();
+        assert_eq!(acknowledge, *b"+");
+    }
+
+    fn receive(client: &mut TcpStream) -> String {
+        let mut reply = Vec::new();
+        let mut byte = [0];
+        while byte != *b"#" {
+            client.read_exact(&mut byte).unwrap();
+            reply.push(byte[0]);
+        }
+        let mut checksum = [0; 2];
+        client.read_exact(&mut checksum).unwrap();
+        String::from_utf8(reply[1..reply.len() - 1].to_vec()).unwrap()
+    }
+
+    const LOOP_FOREVER: &str = "
+            .ORIG x3000
+            LEA R0, LOOP
+    LOOP    JMP R0
+            .END
+    ";
+
+    #[test]
+    fn a_running_program_stops_on_ctrl_c_or_a_disconnect() {
+        let (server, mut client) = serve_in_background(LOOP_FOREVER);
+
+        send(&mut client, "c");
+        client.write_all(&[0x03]).unwrap();
+        assert_eq!(receive(&mut client), "S02");
+        send(&mut client, "?");
+        assert_eq!(receive(&mut client), "S05");
+        send(&mut client, "c");
+        drop(client);
+        assert_eq!(server.join().unwrap(), GdbExit::Disconnected);
+    }
+
+    #[test]
+    fn a_client_can_drive_the_stub_over_tcp() {
+        let (server, mut client) = serve_in_background(STORE_IN_TABLE);
+        let mut exchange = |packet: &str| {
+            send(&mut client, packet);
+            receive(&mut client)
+        };
+
+        assert_eq!(exchange("qSupported:swbreak+"), "PacketSize=1000;swbreak+");
+        assert_eq!(exchange("?"), "S05");
+        assert_eq!(exchange("p8"), "3000");
+        assert_eq!(exchange("c"), "W00");
+        assert_eq!(exchange("D"), "OK");
+        assert_eq!(server.join().unwrap(), GdbExit::Detached);
+    }
+}
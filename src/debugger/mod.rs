@@ -0,0 +1,5 @@
+pub mod breakpoint;
+pub mod gdb;
+pub mod session;
+pub mod target;
+pub mod watchpoint;
@@ -0,0 +1,768 @@
+use crate::{
+    assembler::{disassembler::disassemble, symbol_table::SymbolTable},
+    constants::STREAM_DATA_SEPARATOR,
+    loader::{
+        segment::{Segment, ADDRESS_SPACE_SIZE},
+        validation::LoadWarning,
+    },
+    virtual_machine::{
+        error::VmError,
+        instructions::{JSR, TRAP},
+        lc3_virtual_machine::LC3VirtualMachine,
+        memory_bus::MemoryAccess,
+        register::{Register, AMOUNT_OF_REGISTERS},
+    },
+};
+
+use super::{
+    target::{DebugTarget, Stop},
+    watchpoint::{WatchKind, Watchpoint},
+};
+
+pub const PROGRAM_HALTED_MESSAGE: &str = "Program halted";
+const INVALID_COMMAND_MESSAGE: &str = "Invalid Command";
+/// Keeps the response to `x` inside one datagram.
+const MAXIMUM_WORDS_TO_EXAMINE: u16 = 512;
+
+/// The state behind the debugger protocol: one command in, one response out.
+pub struct DebugSession {
+    target: DebugTarget,
+    symbol_table: SymbolTable,
+}
+
+impl DebugSession {
+    pub fn new(virtual_machine: LC3VirtualMachine, symbol_table: SymbolTable) -> Self {
+        Self {
+            target: DebugTarget::new(virtual_machine),
+            symbol_table,
+        }
+    }
+
+    pub fn virtual_machine(&self) -> &LC3VirtualMachine {
+        &self.target.virtual_machine
+    }
+
+    pub fn execute(&mut self, command: &str) -> String {
+        let words: Vec<&str> = command.split_whitespace().collect();
+        match words[..] {
+            ["n"] => self.step(),
+            ["r"] => self.state_of_registers(),
+            ["next"] => self.step_over(),
+            ["finish"] => self.finish(),
+            ["bt" | "backtrace"] => self.backtrace(),
+            ["c" | "continue"] => self.continue_execution(),
+            ["b" | "break", address] => self.set_breakpoint(address),
+            ["breakpoints"] => self.list_breakpoints(),
+            ["enable", address] => self.enable_breakpoint(address, true),
+            ["disable", address] => self.enable_breakpoint(address, false),
+            ["delete", address] => self.delete_breakpoint(address),
+            ["watch", ref range @ ..] => self.set_watchpoint(WatchKind::Write, range),
+            ["rwatch", ref range @ ..] => self.set_watchpoint(WatchKind::Read, range),
+            ["awatch", ref range @ ..] => self.set_watchpoint(WatchKind::Access, range),
+            ["watchpoints"] => self.list_watchpoints(),
+            ["unwatch", ref range @ ..] => self.delete_watchpoint(range),
+            ["x", address] => self.examine_memory(address, "1"),
+            ["x", address, amount_of_words] => self.examine_memory(address, amount_of_words),
+            ["set", "mem", address, value] => self.set_memory(address, value),
+            ["set", "reg", register, value] => self.set_register(register, value),
+            ["fill", start, end, value] => self.fill_memory(start, end, value),
+            ["load", file] => self.load_file(file, None),
+            ["load", file, address] => self.load_file(file, Some(address)),
+            [amount_of_instructions] => match amount_of_instructions.parse::<u16>() {
+                Ok(amount_of_instructions) => self.step_many(amount_of_instructions),
+                Err(_) => INVALID_COMMAND_MESSAGE.to_string(),
+            },
+            _ => INVALID_COMMAND_MESSAGE.to_string(),
+        }
+    }
+
+    fn step(&mut self) -> String {
+        if let Some(stop) = self.watched_step() {
+            return stop;
+        }
+        self.next_instruction_response()
+    }
+
+    /// Like `step`, but `JSR`, `JSRR` and `TRAP` run until they return.
+    fn step_over(&mut self) -> String {
+        let program_counter = self
+            .target
+            .virtual_machine
+            .read_register(Register::ProgramCounter);
+        let opcode = self
+            .target
+            .virtual_machine
+            .peek_memory(program_counter)
+            .map(|instruction| instruction >> 12);
+        if !matches!(opcode, Some(JSR | TRAP)) {
+            return self.step();
+        }
+        let return_address = program_counter.wrapping_add(1);
+        let call_depth = self.target.virtual_machine.call_stack().len();
+        let has_returned = move |virtual_machine: &LC3VirtualMachine| {
+            virtual_machine.read_register(Register::ProgramCounter) == return_address
+                && virtual_machine.call_stack().len() <= call_depth
+        };
+        match self.run_until(has_returned) {
+            Some(stop) => stop,
+            None => self.next_instruction_response(),
+        }
+    }
+
+    /// Runs until the current subroutine returns with `RET`.
+    fn finish(&mut self) -> String {
+        let call_depth = self.target.virtual_machine.call_stack().len();
+        if call_depth == 0 {
+            return "error: not inside a subroutine".to_string();
+        }
+        let has_returned = move |virtual_machine: &LC3VirtualMachine| {
+            virtual_machine.call_stack().len() < call_depth
+        };
+        match self.run_until(has_returned) {
+            Some(stop) => stop,
+            None => self.next_instruction_response(),
+        }
+    }
+
+    /// The current PC first, then the call site of every subroutine that hasn't returned.
+    fn backtrace(&self) -> String {
+        let program_counter = self
+            .target
+            .virtual_machine
+            .read_register(Register::ProgramCounter);
+        let mut frames = vec![format!(
+            "#0  {}",
+            self.symbol_table.format_address(program_counter)
+        )];
+        for (index, call_frame) in self
+            .target
+            .virtual_machine
+            .call_stack()
+            .iter()
+            .rev()
+            .enumerate()
+        {
+            frames.push(format!(
+                "#{}  {} called {}",
+                index + 1,
+                self.symbol_table.format_address(call_frame.call_address),
+                self.symbol_table
+                    .format_address(call_frame.subroutine_address)
+            ));
+        }
+        frames.join(STREAM_DATA_SEPARATOR)
+    }
+
+    fn next_instruction_response(&self) -> String {
+        let memory_address = self
+            .target
+            .virtual_machine
+            .read_register(Register::ProgramCounter);
+        let location = self.symbol_table.format_address(memory_address);
+        match self.target.virtual_machine.peek_memory(memory_address) {
+            Some(instruction) => format!(
+                "instruction: {instruction:#018b} {} at {location}",
+                disassemble(instruction, memory_address, &self.symbol_table),
+            ),
+            None => format!("instruction: device register at {location}"),
+        }
+    }
+
+    fn step_many(&mut self, amount_of_instructions: u16) -> String {
+        for _ in 0..amount_of_instructions {
+            if let Some(stop) = self.watched_step() {
+                return stop;
+            }
+        }
+        format!("executed {} instructions", amount_of_instructions)
+    }
+
+    /// Runs until the PC reaches an enabled breakpoint, a watchpoint triggers, the program halts or fails.
+    fn continue_execution(&mut self) -> String {
+        self.run_until(|_| false).unwrap_or_default()
+    }
+
+    /// Returns None once `is_done` holds after an instruction, or the response for whatever
+    /// stopped the program first.
+    fn run_until(&mut self, is_done: impl Fn(&LC3VirtualMachine) -> bool) -> Option<String> {
+        let stop = self.target.run_until(is_done)?;
+        Some(self.stop_response(stop))
+    }
+
+    /// Executes one instruction, returning the response when the program has to stop.
+    fn watched_step(&mut self) -> Option<String> {
+        let stop = self.target.step()?;
+        Some(self.stop_response(stop))
+    }
+
+    fn stop_response(&self, stop: Stop) -> String {
+        let (instruction_address, hits) = match stop {
+            Stop::Halted => return PROGRAM_HALTED_MESSAGE.to_string(),
+            Stop::Failed(error) => return self.error_response(error),
+            Stop::Breakpoint(address) => {
+                return format!(
+                    "breakpoint at {}: {}",
+                    self.symbol_table.format_address(address),
+                    self.instruction_at(address)
+                )
+            }
+            Stop::Watchpoints {
+                instruction_address,
+                hits,
+            } => (instruction_address, hits),
+        };
+        let mut reports = vec![format!(
+            "hit by {} {}",
+            self.symbol_table.format_address(instruction_address),
+            self.instruction_at(instruction_address)
+        )];
+        reports.extend(
+            hits.iter()
+                .map(|hit| self.watchpoint_report(&hit.watchpoint, &hit.access)),
+        );
+        reports.push(self.target.virtual_machine.register_summary());
+        reports.join(STREAM_DATA_SEPARATOR)
+    }
+
+    fn watchpoint_report(&self, watchpoint: &Watchpoint, access: &MemoryAccess) -> String {
+        let location = self.symbol_table.format_address(access.address());
+        let access = match access {
+            MemoryAccess::Read { value, .. } => format!("read x{value:04X} from {location}"),
+            MemoryAccess::Write {
+                old_value,
+                new_value,
+                ..
+            } => {
+                let old_value = old_value
+                    .map(|old_value| format!("x{old_value:04X}"))
+                    .unwrap_or_else(|| "unknown".to_string());
+                format!("wrote {location}: {old_value} -> x{new_value:04X}")
+            }
+        };
+        format!(
+            "watchpoint {} {}: {access}",
+            watchpoint.kind,
+            self.format_range(watchpoint.start, watchpoint.end)
+        )
+    }
+
+    fn set_breakpoint(&mut self, address: &str) -> String {
+        let Some(address) = self.symbol_table.parse_address(address) else {
+            return unknown_address(address);
+        };
+        let location = self.symbol_table.format_address(address);
+        if self.target.breakpoints.set(address) {
+            format!("breakpoint set at {location}")
+        } else {
+            format!("breakpoint at {location} already set")
+        }
+    }
+
+    fn list_breakpoints(&self) -> String {
+        if self.target.breakpoints.is_empty() {
+            return "no breakpoints".to_string();
+        }
+        let breakpoints: Vec<String> = self
+            .target
+            .breakpoints
+            .iter()
+            .map(|(address, enabled)| {
+                let state = if enabled { "enabled" } else { "disabled" };
+                format!("{} {state}", self.symbol_table.format_address(address))
+            })
+            .collect();
+        breakpoints.join(STREAM_DATA_SEPARATOR)
+    }
+
+    fn enable_breakpoint(&mut self, address: &str, enabled: bool) -> String {
+        let Some(address) = self.symbol_table.parse_address(address) else {
+            return unknown_address(address);
+        };
+        let location = self.symbol_table.format_address(address);
+        match (
+            self.target.breakpoints.set_enabled(address, enabled),
+            enabled,
+        ) {
+            (true, true) => format!("breakpoint at {location} enabled"),
+            (true, false) => format!("breakpoint at {location} disabled"),
+            (false, _) => format!("no breakpoint at {location}"),
+        }
+    }
+
+    fn delete_breakpoint(&mut self, address: &str) -> String {
+        let Some(address) = self.symbol_table.parse_address(address) else {
+            return unknown_address(address);
+        };
+        let location = self.symbol_table.format_address(address);
+        if self.target.breakpoints.delete(address) {
+            format!("breakpoint at {location} deleted")
+        } else {
+            format!("no breakpoint at {location}")
+        }
+    }
+
+    fn set_watchpoint(&mut self, kind: WatchKind, range: &[&str]) -> String {
+        let (start, end) = match self.parse_range(range) {
+            Ok(range) => range,
+            Err(error) => return error,
+        };
+        let range = self.format_range(start, end);
+        if self.target.watchpoints.add(Watchpoint { kind, start, end }) {
+            format!("watchpoint {kind} set on {range}")
+        } else {
+            format!("watchpoint {kind} on {range} already set")
+        }
+    }
+
+    fn list_watchpoints(&self) -> String {
+        if self.target.watchpoints.is_empty() {
+            return "no watchpoints".to_string();
+        }
+        let watchpoints: Vec<String> = self
+            .target
+            .watchpoints
+            .iter()
+            .map(|watchpoint| {
+                format!(
+                    "{} {}",
+                    watchpoint.kind,
+                    self.format_range(watchpoint.start, watchpoint.end)
+                )
+            })
+            .collect();
+        watchpoints.join(STREAM_DATA_SEPARATOR)
+    }
+
+    fn delete_watchpoint(&mut self, range: &[&str]) -> String {
+        let (start, end) = match self.parse_range(range) {
+            Ok(range) => range,
+            Err(error) => return error,
+        };
+        let range = self.format_range(start, end);
+        if self.target.watchpoints.delete(start, end) {
+            format!("watchpoints on {range} deleted")
+        } else {
+            format!("no watchpoints on {range}")
+        }
+    }
+
+    /// `ADDRESS` or `START END`, both included.
+    fn parse_range(&self, range: &[&str]) -> Result<(u16, u16), String> {
+        let (start, end) = match range {
+            [address] => (*address, *address),
+            [start, end] => (*start, *end),
+            _ => return Err(INVALID_COMMAND_MESSAGE.to_string()),
+        };
+        let start_address = self
+            .symbol_table
+            .parse_address(start)
+            .ok_or_else(|| unknown_address(start))?;
+        let end_address = self
+            .symbol_table
+            .parse_address(end)
+            .ok_or_else(|| unknown_address(end))?;
+        if end_address < start_address {
+            return Err(format!("error: {end} comes before {start}"));
+        }
+        Ok((start_address, end_address))
+    }
+
+    fn format_range(&self, start: u16, end: u16) -> String {
+        if start == end {
+            return self.symbol_table.format_address(start);
+        }
+        format!(
+            "{}..{}",
+            self.symbol_table.format_address(start),
+            self.symbol_table.format_address(end)
+        )
+    }
+
+    /// One line per word: address, hex, signed decimal, ASCII and the instruction it decodes to.
+    fn examine_memory(&mut self, address: &str, amount_of_words: &str) -> String {
+        let Some(start) = self.symbol_table.parse_address(address) else {
+            return unknown_address(address);
+        };
+        let amount_of_words = match amount_of_words.parse::<u16>() {
+            Ok(amount_of_words @ 1..=MAXIMUM_WORDS_TO_EXAMINE) => amount_of_words,
+            _ => {
+                return format!(
+                    "error: the amount of words must go from 1 to {MAXIMUM_WORDS_TO_EXAMINE}"
+                )
+            }
+        };
+        let lines: Vec<String> = (0..amount_of_words)
+            .map(|offset| {
+                let address = start.wrapping_add(offset);
+                let location = self.symbol_table.format_address(address);
+                match self.target.virtual_machine.peek_memory(address) {
+                    Some(word) => {
+                        let ascii = match word {
+                            0x20..=0x7E => word as u8 as char,
+                            _ => '.',
+                        };
+                        format!(
+                            "{location}  x{word:04X}  #{:<6}  {ascii}  {}",
+                            word as i16,
+                            disassemble(word, address, &self.symbol_table)
+                        )
+                    }
+                    None => format!("{location}  device register"),
+                }
+            })
+            .collect();
+        lines.join(STREAM_DATA_SEPARATOR)
+    }
+
+    fn set_memory(&mut self, address: &str, value: &str) -> String {
+        let Some(address) = self.symbol_table.parse_address(address) else {
+            return unknown_address(address);
+        };
+        let Some(value) = self.symbol_table.parse_address(value) else {
+            return invalid_value(value);
+        };
+        match self.target.virtual_machine.memory_write(address, value) {
+            Ok(()) => format!(
+                "{} set to x{value:04X}",
+                self.symbol_table.format_address(address)
+            ),
+            Err(error) => format!("error: {error}"),
+        }
+    }
+
+    fn set_register(&mut self, name: &str, value: &str) -> String {
+        let Some(register) = Register::from_name(name) else {
+            return format!("error: unknown register {name}");
+        };
+        let Some(value) = self.symbol_table.parse_address(value) else {
+            return invalid_value(value);
+        };
+        match register {
+            Register::ProcessorStatus => self.target.virtual_machine.update_processor_status(value),
+            _ => self.target.virtual_machine.update_register(register, value),
+        }
+        format!("{} set to x{value:04X}", name.to_ascii_uppercase())
+    }
+
+    fn fill_memory(&mut self, start: &str, end: &str, value: &str) -> String {
+        let (start, end) = match self.parse_range(&[start, end]) {
+            Ok(range) => range,
+            Err(error) => return error,
+        };
+        let Some(value) = self.symbol_table.parse_address(value) else {
+            return invalid_value(value);
+        };
+        for address in start..=end {
+            if let Err(error) = self.target.virtual_machine.memory_write(address, value) {
+                return format!("error: {error}");
+            }
+        }
+        format!("{} set to x{value:04X}", self.format_range(start, end))
+    }
+
+    /// Writes a program image at its origin, or at `address` when there is one.
+    fn load_file(&mut self, file: &str, address: Option<&str>) -> String {
+        let mut segment = match Segment::read(file, None) {
+            Ok(segment) => segment,
+            Err(error) => return format!("error: {error}"),
+        };
+        if let Some(address) = address {
+            let Some(address) = self.symbol_table.parse_address(address) else {
+                return unknown_address(address);
+            };
+            segment.origin = address;
+        }
+        if segment.words.is_empty() {
+            return format!("error: {file} has no words to load");
+        }
+        if segment.words.len() > ADDRESS_SPACE_SIZE as usize {
+            let warning = LoadWarning::ExceedsAddressSpace {
+                file: file.to_string(),
+                size: segment.words.len(),
+            };
+            return format!("error: {warning}");
+        }
+        if let Err(error) = segment.load_into(&mut self.target.virtual_machine) {
+            return format!("error: {error}");
+        }
+        let end = (segment.origin as usize + segment.words.len() - 1) as u16;
+        format!(
+            "loaded {} words from {file} at {}",
+            segment.words.len(),
+            self.format_range(segment.origin, end)
+        )
+    }
+
+    /// Peeks so that showing an instruction neither fires watchpoints nor touches devices.
+    fn instruction_at(&self, address: u16) -> String {
+        match self.target.virtual_machine.peek_memory(address) {
+            Some(instruction) => disassemble(instruction, address, &self.symbol_table),
+            None => "device register".to_string(),
+        }
+    }
+
+    fn error_response(&self, error: VmError) -> String {
+        let program_counter = self
+            .target
+            .virtual_machine
+            .read_register(Register::ProgramCounter);
+        format!(
+            "error: {error} near {}",
+            self.symbol_table.format_address(program_counter)
+        )
+    }
+
+    /// Registers holding an address near a label are shown as `LABEL+offset` next to their value.
+    fn state_of_registers(&self) -> String {
+        let mut registers = String::new();
+        for index in 0..AMOUNT_OF_REGISTERS as u16 {
+            let Ok(register) = Register::try_from(index) else {
+                continue;
+            };
+            let value = self.target.virtual_machine.read_register(register);
+            registers += &format!("{value:#018b}");
+            let holds_address = index as usize <= Register::ProgramCounter as usize;
+            if let Some(description) = self.symbol_table.describe(value).filter(|_| holds_address) {
+                registers += &format!(" <{description}>");
+            }
+            registers += STREAM_DATA_SEPARATOR;
+        }
+        registers
+    }
+}
+
+fn unknown_address(address: &str) -> String {
+    format!("error: unknown address {address}")
+}
+
+fn invalid_value(value: &str) -> String {
+    format!("error: invalid value {value}")
+}
+
+#[cfg(test)]
+pub mod test {
+    use std::{env, fs, process};
+
+    use super::{DebugSession, PROGRAM_HALTED_MESSAGE};
+    use crate::{debugger::target::test::assembled_program, virtual_machine::register::Register};
+
+    fn debug_session(source: &str) -> DebugSession {
+        let (virtual_machine, symbol_table) = assembled_program(source);
+        DebugSession::new(virtual_machine, symbol_table)
+    }
+
+    const COUNT_DOWN: &str = "
+            .ORIG x3000
+            AND R0, R0, #0
+            ADD R0, R0, #3
+    LOOP    ADD R0, R0, #-1
+            BRp LOOP
+            HALT
+            .END
+    ";
+
+    #[test]
+    fn continue_stops_at_breakpoints_set_by_label() {
+        let mut debug_session = debug_session(COUNT_DOWN);
+
+        assert_eq!(
+            debug_session.execute("break LOOP+1"),
+            "breakpoint set at x3003 <LOOP+1>"
+        );
+        assert_eq!(
+            debug_session.execute("continue"),
+            "breakpoint at x3003 <LOOP+1>: BRp x3002 <LOOP>"
+        );
+        assert_eq!(
+            debug_session.virtual_machine().read_register(Register::R0),
+            2
+        );
+        debug_session.execute("disable x3003");
+        assert_eq!(debug_session.execute("c"), PROGRAM_HALTED_MESSAGE);
+    }
+
+    #[test]
+    fn write_watchpoints_report_the_instruction_and_the_values() {
+        let mut debug_session = debug_session(
+            "
+            .ORIG x3000
+            LEA R1, TABLE
+            AND R0, R0, #0
+            ADD R0, R0, #5
+    STORE   STR R0, R1, #1
+            LDR R2, R1, #1
+            HALT
+    TABLE   .BLKW 4
+            .END
+        ",
+        );
+
+        assert_eq!(
+            debug_session.execute("watch TABLE TABLE+3"),
+            "watchpoint write set on x3006 <TABLE>..x3009 <TABLE+3>"
+        );
+        assert_eq!(
+            debug_session.execute("c"),
+            "hit by x3003 <STORE> STR R0, R1, #1::\
+             watchpoint write x3006 <TABLE>..x3009 <TABLE+3>: wrote x3007 <TABLE+1>: x0000 -> x0005::\
+             R0=x0005 R1=x3006 R2=x0000 R3=x0000 R4=x0000 R5=x0000 R6=x0000 R7=x0000 PC=x3004 PSR=x0001"
+        );
+        debug_session.execute("unwatch TABLE TABLE+3");
+        debug_session.execute("rwatch TABLE+1");
+        assert!(debug_session
+            .execute("c")
+            .contains("watchpoint read x3007 <TABLE+1>: read x0005 from x3007 <TABLE+1>"));
+        assert_eq!(debug_session.execute("c"), PROGRAM_HALTED_MESSAGE);
+    }
+
+    #[test]
+    fn showing_the_next_instruction_is_not_a_memory_access() {
+        let mut debug_session = debug_session(COUNT_DOWN);
+        debug_session.execute("awatch x3000 x3004");
+
+        assert_eq!(
+            debug_session.execute("n"),
+            "instruction: 0b0001000000100011 ADD R0, R0, #3 at x3001"
+        );
+        assert!(debug_session.target.take_memory_accesses().is_empty());
+        debug_session.execute("set reg PC xFE02");
+        assert_eq!(
+            debug_session.next_instruction_response(),
+            "instruction: device register at xFE02"
+        );
+    }
+
+    #[test]
+    fn load_refuses_images_bigger_than_the_address_space() {
+        let mut debug_session = debug_session(COUNT_DOWN);
+        let directory = env::temp_dir();
+        let full = directory.join(format!("lc3_vm_load_{}_full.obj", process::id()));
+        let too_big = directory.join(format!("lc3_vm_load_{}_too_big.obj", process::id()));
+        fs::write(&full, vec![0; 2 + 0x10000 * 2]).unwrap();
+        fs::write(&too_big, vec![0; 2 + 0x10001 * 2]).unwrap();
+
+        assert_eq!(
+            debug_session.execute(&format!("load {}", full.display())),
+            format!("loaded 65536 words from {} at x0000..xFFFF", full.display())
+        );
+        assert_eq!(
+            debug_session.execute(&format!("load {}", too_big.display())),
+            format!(
+                "error: {} holds 65537 words, more than the 65536 of the address space",
+                too_big.display()
+            )
+        );
+        fs::remove_file(full).unwrap();
+        fs::remove_file(too_big).unwrap();
+    }
+
+    #[test]
+    fn memory_can_be_examined_and_patched() {
+        let mut debug_session = debug_session(COUNT_DOWN);
+
+        assert_eq!(
+            debug_session.execute("x LOOP 2"),
+            "x3002 <LOOP>  x103F  #4159    .  ADD R0, R0, #-1::\
+             x3003 <LOOP+1>  x03FE  #1022    .  BRp x3002 <LOOP>"
+        );
+        assert_eq!(
+            debug_session.execute("set mem x4000 #-2"),
+            "x4000 set to xFFFE"
+        );
+        assert_eq!(
+            debug_session.execute("x x4000"),
+            "x4000  xFFFE  #-2      .  TRAP xFE"
+        );
+        debug_session.execute("fill x4001 x4003 x41");
+        assert_eq!(
+            debug_session.execute("x x4003"),
+            "x4003  x0041  #65      A  NOP"
+        );
+        assert_eq!(debug_session.execute("set reg r3 LOOP"), "R3 set to x3002");
+        assert_eq!(
+            debug_session.virtual_machine().read_register(Register::R3),
+            0x3002
+        );
+        assert_eq!(
+            debug_session.execute("set reg R9 1"),
+            "error: unknown register R9"
+        );
+    }
+
+    const NESTED_CALLS: &str = "
+            .ORIG x3000
+            JSR OUTER
+            HALT
+    OUTER   ADD R6, R7, #0
+            JSR INNER
+            ADD R7, R6, #0
+            RET
+    INNER   ADD R0, R0, #1
+            RET
+            .END
+    ";
+
+    #[test]
+    fn backtrace_shows_the_calls_that_have_not_returned() {
+        let mut debug_session = debug_session(NESTED_CALLS);
+        debug_session.execute("b INNER");
+        debug_session.execute("c");
+
+        assert_eq!(
+            debug_session.execute("bt"),
+            "#0  x3006 <INNER>::\
+             #1  x3003 <OUTER+1> called x3006 <INNER>::\
+             #2  x3000 called x3002 <OUTER>"
+        );
+        assert_eq!(
+            debug_session.execute("finish"),
+            "instruction: 0b0001111110100000 ADD R7, R6, #0 at x3004 <OUTER+2>"
+        );
+        assert_eq!(debug_session.execute("backtrace").matches('#').count(), 2);
+    }
+
+    #[test]
+    fn next_runs_subroutine_calls_as_one_step() {
+        let mut debug_session = debug_session(NESTED_CALLS);
+
+        assert_eq!(
+            debug_session.execute("next"),
+            "instruction: 0b1111000000100101 HALT at x3001"
+        );
+        assert_eq!(
+            debug_session.virtual_machine().read_register(Register::R0),
+            1
+        );
+        assert_eq!(
+            debug_session.execute("finish"),
+            "error: not inside a subroutine"
+        );
+        debug_session.execute("set reg PC OUTER+1");
+        debug_session.execute("b INNER+1");
+        assert_eq!(
+            debug_session.execute("next"),
+            "breakpoint at x3007 <INNER+1>: RET"
+        );
+    }
+
+    #[test]
+    fn breakpoints_can_be_listed_and_deleted() {
+        let mut debug_session = debug_session(COUNT_DOWN);
+        debug_session.execute("b LOOP");
+        debug_session.execute("b x3004");
+        debug_session.execute("disable LOOP");
+
+        assert_eq!(
+            debug_session.execute("breakpoints"),
+            "x3002 <LOOP> disabled::x3004 <LOOP+2> enabled"
+        );
+        assert_eq!(
+            debug_session.execute("delete x3005"),
+            "no breakpoint at x3005 <LOOP+3>"
+        );
+        assert_eq!(
+            debug_session.execute("b MISSING"),
+            "error: unknown address MISSING"
+        );
+    }
+}
@@ -0,0 +1,169 @@
+use std::{cell::RefCell, mem, rc::Rc};
+
+use crate::virtual_machine::{
+    error::VmError,
+    lc3_virtual_machine::{LC3VirtualMachine, StepOutcome},
+    memory_bus::MemoryAccess,
+    register::Register,
+};
+
+use super::{
+    breakpoint::Breakpoints,
+    watchpoint::{Watchpoint, Watchpoints},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WatchpointHit {
+    pub watchpoint: Watchpoint,
+    pub access: MemoryAccess,
+}
+
+/// Why the debugged program stopped, for each front-end to report its own way.
+#[derive(Debug)]
+pub enum Stop {
+    Halted,
+    Failed(VmError),
+    Breakpoint(u16),
+    Watchpoints {
+        instruction_address: u16,
+        hits: Vec<WatchpointHit>,
+    },
+}
+
+/// The machine being debugged with its stop points, shared by the debugger and the GDB stub.
+pub struct DebugTarget {
+    pub virtual_machine: LC3VirtualMachine,
+    pub breakpoints: Breakpoints,
+    pub watchpoints: Watchpoints,
+    memory_accesses: Rc<RefCell<Vec<MemoryAccess>>>,
+}
+
+impl DebugTarget {
+    pub fn new(mut virtual_machine: LC3VirtualMachine) -> Self {
+        let memory_accesses = Rc::new(RefCell::new(Vec::new()));
+        let hook_memory_accesses = Rc::clone(&memory_accesses);
+        virtual_machine
+            .set_memory_hook(move |access| hook_memory_accesses.borrow_mut().push(access));
+        Self {
+            virtual_machine,
+            breakpoints: Breakpoints::new(),
+            watchpoints: Watchpoints::new(),
+            memory_accesses,
+        }
+    }
+
+    pub fn into_virtual_machine(mut self) -> LC3VirtualMachine {
+        self.virtual_machine.clear_memory_hook();
+        self.virtual_machine
+    }
+
+    /// The accesses recorded since the last call.
+    pub fn take_memory_accesses(&self) -> Vec<MemoryAccess> {
+        mem::take(&mut *self.memory_accesses.borrow_mut())
+    }
+
+    /// Executes one instruction, returning why the program has to stop if it does.
+    pub fn step(&mut self) -> Option<Stop> {
+        let instruction_address = self.virtual_machine.read_register(Register::ProgramCounter);
+        self.take_memory_accesses();
+        match self.virtual_machine.next_instruction() {
+            Ok(StepOutcome::Halted) => return Some(Stop::Halted),
+            Ok(StepOutcome::Continue) => {}
+            Err(error) => return Some(Stop::Failed(error)),
+        }
+        let hits: Vec<WatchpointHit> = self
+            .take_memory_accesses()
+            .into_iter()
+            .filter_map(|access| {
+                let watchpoint = *self.watchpoints.triggered_by(&access)?;
+                Some(WatchpointHit { watchpoint, access })
+            })
+            .collect();
+        (!hits.is_empty()).then_some(Stop::Watchpoints {
+            instruction_address,
+            hits,
+        })
+    }
+
+    /// Runs until the PC reaches an enabled breakpoint, a watchpoint triggers, the program halts
+    /// or fails. Returns None once `is_done` holds after an instruction instead.
+    pub fn run_until(
+        &mut self,
+        mut is_done: impl FnMut(&LC3VirtualMachine) -> bool,
+    ) -> Option<Stop> {
+        loop {
+            if let Some(stop) = self.step() {
+                return Some(stop);
+            }
+            if is_done(&self.virtual_machine) {
+                return None;
+            }
+            let program_counter = self.virtual_machine.read_register(Register::ProgramCounter);
+            if self.breakpoints.is_enabled_at(program_counter) {
+                return Some(Stop::Breakpoint(program_counter));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::{DebugTarget, Stop};
+    use crate::{
+        assembler::{lc3_assembler::assemble, symbol_table::SymbolTable},
+        debugger::watchpoint::{WatchKind, Watchpoint},
+        loader::segment::Segment,
+        virtual_machine::{console::BufferConsole, lc3_virtual_machine::LC3VirtualMachine},
+    };
+
+    /// Assembles `source` and loads it like a program file, for the debugger front-ends' tests.
+    pub fn assembled_program(source: &str) -> (LC3VirtualMachine, SymbolTable) {
+        let object_file = assemble(source).unwrap();
+        let mut virtual_machine =
+            LC3VirtualMachine::with_console(object_file.origin, BufferConsole::default());
+        Segment::new("test.obj", object_file.origin, object_file.words)
+            .load_into(&mut virtual_machine)
+            .unwrap();
+        (virtual_machine, object_file.symbol_table)
+    }
+
+    const STORE_TWICE: &str = "
+            .ORIG x3000
+            LEA R1, TABLE
+            STR R0, R1, #0
+            STR R0, R1, #1
+            HALT
+    TABLE   .BLKW 2
+            .END
+    ";
+
+    #[test]
+    fn running_stops_at_breakpoints_watchpoints_and_the_end() {
+        let (virtual_machine, _) = assembled_program(STORE_TWICE);
+        let mut debug_target = DebugTarget::new(virtual_machine);
+        debug_target.breakpoints.set(0x3002);
+        debug_target.watchpoints.add(Watchpoint {
+            kind: WatchKind::Write,
+            start: 0x3005,
+            end: 0x3005,
+        });
+
+        assert!(matches!(
+            debug_target.run_until(|_| false),
+            Some(Stop::Breakpoint(0x3002))
+        ));
+        let Some(Stop::Watchpoints {
+            instruction_address,
+            hits,
+        }) = debug_target.run_until(|_| false)
+        else {
+            panic!("expected a watchpoint stop");
+        };
+        assert_eq!(instruction_address, 0x3002);
+        assert_eq!(hits[0].access.address(), 0x3005);
+        assert!(matches!(
+            debug_target.run_until(|_| false),
+            Some(Stop::Halted)
+        ));
+    }
+}
@@ -0,0 +1,130 @@
+use std::fmt::Display;
+
+use crate::virtual_machine::memory_bus::MemoryAccess;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WatchKind {
+    Read,
+    Write,
+    Access,
+}
+
+impl Display for WatchKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WatchKind::Read => write!(f, "read"),
+            WatchKind::Write => write!(f, "write"),
+            WatchKind::Access => write!(f, "access"),
+        }
+    }
+}
+
+/// Watches the addresses from `start` to `end`, both included.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Watchpoint {
+    pub kind: WatchKind,
+    pub start: u16,
+    pub end: u16,
+}
+
+impl Watchpoint {
+    pub fn triggered_by(&self, access: &MemoryAccess) -> bool {
+        let kind_matches = matches!(
+            (self.kind, access),
+            (WatchKind::Access, _)
+                | (WatchKind::Read, MemoryAccess::Read { .. })
+                | (WatchKind::Write, MemoryAccess::Write { .. })
+        );
+        kind_matches && (self.start..=self.end).contains(&access.address())
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Watchpoints {
+    watchpoints: Vec<Watchpoint>,
+}
+
+impl Watchpoints {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns false if the same watchpoint was already set.
+    pub fn add(&mut self, watchpoint: Watchpoint) -> bool {
+        if self.watchpoints.contains(&watchpoint) {
+            return false;
+        }
+        self.watchpoints.push(watchpoint);
+        true
+    }
+
+    pub fn remove(&mut self, watchpoint: &Watchpoint) -> bool {
+        let amount_of_watchpoints = self.watchpoints.len();
+        self.watchpoints.retain(|current| current != watchpoint);
+        self.watchpoints.len() != amount_of_watchpoints
+    }
+
+    /// Deletes every watchpoint on exactly that range, whatever its kind.
+    pub fn delete(&mut self, start: u16, end: u16) -> bool {
+        let amount_of_watchpoints = self.watchpoints.len();
+        self.watchpoints
+            .retain(|watchpoint| (watchpoint.start, watchpoint.end) != (start, end));
+        self.watchpoints.len() != amount_of_watchpoints
+    }
+
+    pub fn triggered_by(&self, access: &MemoryAccess) -> Option<&Watchpoint> {
+        self.watchpoints
+            .iter()
+            .find(|watchpoint| watchpoint.triggered_by(access))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.watchpoints.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Watchpoint> {
+        self.watchpoints.iter()
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::{WatchKind, Watchpoint, Watchpoints};
+    use crate::virtual_machine::memory_bus::MemoryAccess;
+
+    #[test]
+    fn watchpoints_trigger_on_their_kind_of_access_inside_the_range() {
+        let mut watchpoints = Watchpoints::new();
+        watchpoints.add(Watchpoint {
+            kind: WatchKind::Write,
+            start: 0x4000,
+            end: 0x400F,
+        });
+        let read = MemoryAccess::Read {
+            address: 0x4004,
+            value: 0,
+        };
+        let write = MemoryAccess::Write {
+            address: 0x4004,
+            old_value: Some(0),
+            new_value: 1,
+        };
+        let write_outside = MemoryAccess::Write {
+            address: 0x4010,
+            old_value: Some(0),
+            new_value: 1,
+        };
+
+        assert_eq!(watchpoints.triggered_by(&read), None);
+        assert!(watchpoints.triggered_by(&write).is_some());
+        assert_eq!(watchpoints.triggered_by(&write_outside), None);
+        assert!(Watchpoint {
+            kind: WatchKind::Access,
+            start: 0x4004,
+            end: 0x4004
+        }
+        .triggered_by(&read));
+        assert!(watchpoints.delete(0x4000, 0x400F));
+        assert!(watchpoints.is_empty());
+    }
+}
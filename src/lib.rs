@@ -1,3 +1,6 @@
+pub mod assembler;
 pub mod constants;
+pub mod debugger;
+pub mod loader;
 pub mod virtual_machine;
 pub mod virtual_machine_start;
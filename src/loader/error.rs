@@ -0,0 +1,84 @@
+use std::{fmt::Display, io::Error};
+
+use super::{format::ImageFormat, validation::LoadWarning};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Overlap {
+    pub first_file: String,
+    pub second_file: String,
+    pub start: u16,
+    pub end: u16,
+}
+
+#[derive(Debug)]
+pub enum LoadError {
+    Read {
+        file: String,
+        error: Error,
+    },
+    InvalidWord {
+        file: String,
+        line: usize,
+        format: ImageFormat,
+        word: String,
+    },
+    MissingOrigin(String),
+    InvalidHeader(String),
+    Truncated(String),
+    MultipleOrigins(String),
+    NoProgramFiles,
+    UnknownEntryFile(String),
+    OverlappingSegments(Vec<Overlap>),
+    Rejected(Vec<LoadWarning>),
+}
+
+impl Display for Overlap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} and {} both load x{:04X}-x{:04X}",
+            self.first_file, self.second_file, self.start, self.end
+        )
+    }
+}
+
+impl Display for LoadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LoadError::Read { file, error } => write!(f, "couldn't read {file}: {error}"),
+            LoadError::InvalidWord {
+                file,
+                line,
+                format,
+                word,
+            } => write!(f, "{file}:{line}: {word} is not a {format} word"),
+            LoadError::MissingOrigin(file) => write!(f, "{file} has no origin"),
+            LoadError::InvalidHeader(file) => write!(f, "{file} has an invalid header"),
+            LoadError::Truncated(file) => write!(f, "{file} ends in the middle of a word"),
+            LoadError::MultipleOrigins(file) => {
+                write!(f, "{file} has more than one origin, which isn't supported")
+            }
+            LoadError::NoProgramFiles => write!(f, "no program files to load"),
+            LoadError::UnknownEntryFile(file) => {
+                write!(f, "entry file {file} is not one of the loaded files")
+            }
+            LoadError::OverlappingSegments(overlaps) => {
+                let overlaps: Vec<String> = overlaps.iter().map(Overlap::to_string).collect();
+                write!(f, "overlapping segments: {}", overlaps.join("; "))
+            }
+            LoadError::Rejected(warnings) => {
+                let warnings: Vec<String> = warnings.iter().map(LoadWarning::to_string).collect();
+                write!(f, "refused in strict mode: {}", warnings.join("; "))
+            }
+        }
+    }
+}
+
+impl std::error::Error for LoadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            LoadError::Read { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
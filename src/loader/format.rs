@@ -0,0 +1,263 @@
+use std::{fmt::Display, path::Path, str::FromStr};
+
+use byteorder::{LittleEndian, ReadBytesExt};
+
+use super::{error::LoadError, segment::Segment};
+
+const COMMENT_STARTS: [&str; 3] = [";", "#", "//"];
+const HEX_WORD_LENGTH: usize = 4;
+const BINARY_WORD_LENGTH: usize = 16;
+/// lc3tools object files start with this magic number followed by a two byte version.
+const LC3TOOLS_MAGIC: [u8; 4] = [0x1C, 0x30, 0x15, 0xC0];
+const LC3TOOLS_HEADER_LENGTH: usize = 6;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageFormat {
+    Object,
+    Hex,
+    Binary,
+    Lc3Tools,
+}
+
+impl FromStr for ImageFormat {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "obj" => Ok(ImageFormat::Object),
+            "hex" => Ok(ImageFormat::Hex),
+            "bin" => Ok(ImageFormat::Binary),
+            "lc3tools" => Ok(ImageFormat::Lc3Tools),
+            _ => Err(format!(
+                "unknown image format {name}, use obj, hex, bin or lc3tools"
+            )),
+        }
+    }
+}
+
+impl Display for ImageFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ImageFormat::Object => write!(f, "obj"),
+            ImageFormat::Hex => write!(f, "hex"),
+            ImageFormat::Binary => write!(f, "bin"),
+            ImageFormat::Lc3Tools => write!(f, "lc3tools"),
+        }
+    }
+}
+
+impl ImageFormat {
+    /// lc3tools files are recognized by their magic number, as they share the `.obj` extension.
+    /// Otherwise the extension decides when it is a known one, else the content is inspected.
+    pub fn detect(file: &str, bytes: &[u8]) -> Self {
+        if bytes.starts_with(&LC3TOOLS_MAGIC) {
+            return ImageFormat::Lc3Tools;
+        }
+        let extension = Path::new(file)
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(str::to_ascii_lowercase);
+        if let Some(format) = extension.and_then(|extension| extension.parse().ok()) {
+            return format;
+        }
+        let Ok(text) = std::str::from_utf8(bytes) else {
+            return ImageFormat::Object;
+        };
+        let words: Vec<String> = text.lines().filter_map(strip_comment).collect();
+        if words.is_empty() {
+            return ImageFormat::Object;
+        }
+        if words.iter().all(|word| parse_binary_word(word).is_some()) {
+            return ImageFormat::Binary;
+        }
+        if words.iter().all(|word| parse_hex_word(word).is_some()) {
+            return ImageFormat::Hex;
+        }
+        ImageFormat::Object
+    }
+
+    pub fn parse(&self, file: &str, bytes: &[u8]) -> Result<Segment, LoadError> {
+        match self {
+            ImageFormat::Object => Segment::from_object_bytes(file, bytes),
+            ImageFormat::Hex => parse_text_image(file, bytes, *self, parse_hex_word),
+            ImageFormat::Binary => parse_text_image(file, bytes, *self, parse_binary_word),
+            ImageFormat::Lc3Tools => parse_lc3tools_image(file, bytes),
+        }
+    }
+}
+
+/// The word on a line without its comment and whitespace, `None` for blank lines.
+fn strip_comment(line: &str) -> Option<String> {
+    let end = COMMENT_STARTS
+        .iter()
+        .filter_map(|comment_start| line.find(comment_start))
+        .min()
+        .unwrap_or(line.len());
+    let word: String = line[..end]
+        .chars()
+        .filter(|char| !char.is_whitespace() && *char != '_')
+        .collect();
+    (!word.is_empty()).then_some(word)
+}
+
+fn parse_hex_word(word: &str) -> Option<u16> {
+    let digits = word
+        .strip_prefix("0x")
+        .or_else(|| word.strip_prefix(['x', 'X']))
+        .unwrap_or(word);
+    if digits.is_empty() || digits.len() > HEX_WORD_LENGTH {
+        return None;
+    }
+    u16::from_str_radix(digits, 16).ok()
+}
+
+fn parse_binary_word(word: &str) -> Option<u16> {
+    if word.len() != BINARY_WORD_LENGTH || !word.chars().all(|char| char == '0' || char == '1') {
+        return None;
+    }
+    u16::from_str_radix(word, 2).ok()
+}
+
+/// Text images hold one word per line, the first one being the origin like in object files.
+fn parse_text_image(
+    file: &str,
+    bytes: &[u8],
+    format: ImageFormat,
+    parse_word: fn(&str) -> Option<u16>,
+) -> Result<Segment, LoadError> {
+    let text = String::from_utf8_lossy(bytes);
+    let mut words = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        let Some(word) = strip_comment(line) else {
+            continue;
+        };
+        let word = parse_word(&word).ok_or_else(|| LoadError::InvalidWord {
+            file: file.to_string(),
+            line: index + 1,
+            format,
+            word,
+        })?;
+        words.push(word);
+    }
+    if words.is_empty() {
+        return Err(LoadError::MissingOrigin(file.to_string()));
+    }
+    let origin = words.remove(0);
+    Ok(Segment::new(file, origin, words))
+}
+
+/// After the header every word is its little-endian value, a byte telling if it is an origin
+/// and the source line it came from, as a little-endian u32 length and its bytes.
+fn parse_lc3tools_image(file: &str, bytes: &[u8]) -> Result<Segment, LoadError> {
+    if !bytes.starts_with(&LC3TOOLS_MAGIC) || bytes.len() < LC3TOOLS_HEADER_LENGTH {
+        return Err(LoadError::InvalidHeader(file.to_string()));
+    }
+    let truncated = || LoadError::Truncated(file.to_string());
+    let mut reader = &bytes[LC3TOOLS_HEADER_LENGTH..];
+    let mut origin = None;
+    let mut words = Vec::new();
+    while !reader.is_empty() {
+        let value = reader.read_u16::<LittleEndian>().map_err(|_| truncated())?;
+        let is_origin = reader.read_u8().map_err(|_| truncated())? != 0;
+        let line_length = reader.read_u32::<LittleEndian>().map_err(|_| truncated())? as usize;
+        reader = reader.get(line_length..).ok_or_else(truncated)?;
+        match (is_origin, origin) {
+            (true, None) => origin = Some(value),
+            (true, Some(_)) => return Err(LoadError::MultipleOrigins(file.to_string())),
+            (false, None) => return Err(LoadError::MissingOrigin(file.to_string())),
+            (false, Some(_)) => words.push(value),
+        }
+    }
+    let origin = origin.ok_or_else(|| LoadError::MissingOrigin(file.to_string()))?;
+    Ok(Segment::new(file, origin, words))
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::ImageFormat;
+    use crate::loader::error::LoadError;
+
+    #[test]
+    fn every_format_gives_the_same_image() {
+        let object = [0x30, 0x00, 0xE0, 0x02, 0xF0, 0x25];
+        let hex = "; hello\n3000\n\nE002 ; LEA\nxF025\n";
+        let binary = "0011000000000000\n1110_0000_0000_0010\n// HALT\n1111000000100101\n";
+
+        let object = ImageFormat::Object.parse("a.obj", &object).unwrap();
+        let hex = ImageFormat::Hex.parse("a.hex", hex.as_bytes()).unwrap();
+        let binary = ImageFormat::Binary
+            .parse("a.bin", binary.as_bytes())
+            .unwrap();
+
+        assert_eq!(object.origin, 0x3000);
+        assert_eq!(object.words, vec![0xE002, 0xF025]);
+        assert_eq!((hex.origin, &hex.words), (object.origin, &object.words));
+        assert_eq!(
+            (binary.origin, &binary.words),
+            (object.origin, &object.words)
+        );
+    }
+
+    #[test]
+    fn format_is_detected_from_the_extension_or_the_content() {
+        assert_eq!(ImageFormat::detect("a.HEX", b""), ImageFormat::Hex);
+        assert_eq!(ImageFormat::detect("a.obj", b"3000\n"), ImageFormat::Object);
+        assert_eq!(
+            ImageFormat::detect("a.txt", b"0011000000000000\n"),
+            ImageFormat::Binary
+        );
+        assert_eq!(
+            ImageFormat::detect("a.txt", b"3000\nF025\n"),
+            ImageFormat::Hex
+        );
+        assert_eq!(
+            ImageFormat::detect("a", &[0x30, 0x00, 0xF0, 0x25]),
+            ImageFormat::Object
+        );
+    }
+
+    /// A word of an lc3tools object file with its source line.
+    fn lc3tools_entry(value: u16, is_origin: bool, line: &str) -> Vec<u8> {
+        let mut entry = value.to_le_bytes().to_vec();
+        entry.push(is_origin as u8);
+        entry.extend((line.len() as u32).to_le_bytes());
+        entry.extend(line.as_bytes());
+        entry
+    }
+
+    #[test]
+    fn lc3tools_object_files_are_detected_and_loaded() {
+        let mut bytes = vec![0x1C, 0x30, 0x15, 0xC0, 0x01, 0x01];
+        bytes.extend(lc3tools_entry(0x3000, true, ".ORIG x3000"));
+        bytes.extend(lc3tools_entry(0xE002, false, "LEA R0, HELLO"));
+        bytes.extend(lc3tools_entry(0xF025, false, "HALT"));
+
+        let format = ImageFormat::detect("a.obj", &bytes);
+        let segment = format.parse("a.obj", &bytes).unwrap();
+
+        assert_eq!(format, ImageFormat::Lc3Tools);
+        assert_eq!(segment.origin, 0x3000);
+        assert_eq!(segment.words, vec![0xE002, 0xF025]);
+        assert!(matches!(
+            format.parse("a.obj", &bytes[..bytes.len() - 1]),
+            Err(LoadError::Truncated(_))
+        ));
+        bytes.extend(lc3tools_entry(0x4000, true, ".ORIG x4000"));
+        assert!(matches!(
+            format.parse("a.obj", &bytes),
+            Err(LoadError::MultipleOrigins(_))
+        ));
+    }
+
+    #[test]
+    fn invalid_words_report_their_line() {
+        let error = ImageFormat::Hex
+            .parse("a.hex", b"3000\nF025\nHALT\n")
+            .unwrap_err();
+
+        assert!(matches!(
+            error,
+            LoadError::InvalidWord { line: 3, ref word, .. } if word == "HALT"
+        ));
+    }
+}
@@ -0,0 +1,208 @@
+use std::fs;
+
+use byteorder::{BigEndian, ReadBytesExt};
+
+use crate::virtual_machine::{error::VmError, lc3_virtual_machine::LC3VirtualMachine};
+
+use super::{
+    error::{LoadError, Overlap},
+    format::ImageFormat,
+};
+
+pub const ADDRESS_SPACE_SIZE: u32 = 1 << 16;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Segment {
+    pub file: String,
+    pub origin: u16,
+    pub words: Vec<u16>,
+    pub has_trailing_byte: bool,
+}
+
+impl Segment {
+    pub fn new(file: impl Into<String>, origin: u16, words: Vec<u16>) -> Self {
+        Self {
+            file: file.into(),
+            origin,
+            words,
+            has_trailing_byte: false,
+        }
+    }
+
+    /// Object files are the origin followed by the words, all big-endian.
+    pub fn from_object_bytes(file: &str, bytes: &[u8]) -> Result<Self, LoadError> {
+        let mut reader = bytes;
+        let origin = reader
+            .read_u16::<BigEndian>()
+            .map_err(|_| LoadError::MissingOrigin(file.to_string()))?;
+        let mut words = Vec::new();
+        while let Ok(word) = reader.read_u16::<BigEndian>() {
+            words.push(word);
+        }
+        Ok(Self {
+            has_trailing_byte: bytes.len() % 2 == 1,
+            ..Self::new(file, origin, words)
+        })
+    }
+
+    /// Reads an object, hex or binary text image, detecting the format when none is given.
+    pub fn read(file: &str, format: Option<ImageFormat>) -> Result<Self, LoadError> {
+        let bytes = fs::read(file).map_err(|error| LoadError::Read {
+            file: file.to_string(),
+            error,
+        })?;
+        format
+            .unwrap_or_else(|| ImageFormat::detect(file, &bytes))
+            .parse(file, &bytes)
+    }
+
+    /// Half-open address ranges, split in two when the segment wraps past xFFFF.
+    pub fn address_ranges(&self) -> Vec<(u32, u32)> {
+        let start = self.origin as u32;
+        let end = start + self.words.len() as u32;
+        if end <= ADDRESS_SPACE_SIZE {
+            return vec![(start, end)];
+        }
+        vec![
+            (start, ADDRESS_SPACE_SIZE),
+            (0, (end - ADDRESS_SPACE_SIZE).min(ADDRESS_SPACE_SIZE)),
+        ]
+    }
+
+    pub fn load_into(&self, virtual_machine: &mut LC3VirtualMachine) -> Result<(), VmError> {
+        let mut memory_address = self.origin;
+        for word in &self.words {
+            virtual_machine.memory_write(memory_address, *word)?;
+            memory_address = memory_address.wrapping_add(1);
+        }
+        Ok(())
+    }
+}
+
+pub fn find_overlaps(segments: &[Segment]) -> Vec<Overlap> {
+    let mut overlaps = Vec::new();
+    for (index, first) in segments.iter().enumerate() {
+        for second in &segments[index + 1..] {
+            for (first_start, first_end) in first.address_ranges() {
+                for (second_start, second_end) in second.address_ranges() {
+                    let start = first_start.max(second_start);
+                    let end = first_end.min(second_end);
+                    if start < end {
+                        overlaps.push(Overlap {
+                            first_file: first.file.clone(),
+                            second_file: second.file.clone(),
+                            start: start as u16,
+                            end: (end - 1) as u16,
+                        });
+                    }
+                }
+            }
+        }
+    }
+    overlaps
+}
+
+pub fn check_overlaps(segments: &[Segment]) -> Result<(), LoadError> {
+    let overlaps = find_overlaps(segments);
+    if !overlaps.is_empty() {
+        return Err(LoadError::OverlappingSegments(overlaps));
+    }
+    Ok(())
+}
+
+/// The entry file defaults to the first one, its origin becomes the starting PC.
+pub fn entry_point(segments: &[Segment], entry_file: Option<&str>) -> Result<u16, LoadError> {
+    let entry_segment = match entry_file {
+        Some(entry_file) => segments
+            .iter()
+            .find(|segment| segment.file == entry_file)
+            .ok_or_else(|| LoadError::UnknownEntryFile(entry_file.to_string()))?,
+        None => segments.first().ok_or(LoadError::NoProgramFiles)?,
+    };
+    Ok(entry_segment.origin)
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::{check_overlaps, entry_point, find_overlaps, Segment};
+    use crate::{
+        loader::error::{LoadError, Overlap},
+        virtual_machine::{console::BufferConsole, lc3_virtual_machine::LC3VirtualMachine},
+    };
+
+    fn segment(file: &str, origin: u16, length: usize) -> Segment {
+        Segment::new(file, origin, vec![0xF025; length])
+    }
+
+    #[test]
+    fn can_read_an_object_file() {
+        let segment = Segment::from_object_bytes("main.obj", &[0x30, 0x00, 0xF0, 0x25]).unwrap();
+
+        assert_eq!(segment.origin, 0x3000);
+        assert_eq!(segment.words, vec![0xF025]);
+    }
+
+    #[test]
+    fn segments_of_several_files_are_loaded_into_one_machine() {
+        let segments = vec![
+            segment("main.obj", 0x3000, 2),
+            segment("lib.obj", 0x4000, 3),
+        ];
+        let mut virtual_machine = LC3VirtualMachine::with_console(0x3000, BufferConsole::default());
+        check_overlaps(&segments).unwrap();
+        for segment in &segments {
+            segment.load_into(&mut virtual_machine).unwrap();
+        }
+
+        assert_eq!(virtual_machine.memory_read(0x3001).unwrap(), 0xF025);
+        assert_eq!(virtual_machine.memory_read(0x4002).unwrap(), 0xF025);
+        assert_eq!(virtual_machine.memory_read(0x4003).unwrap(), 0);
+    }
+
+    #[test]
+    fn overlapping_segments_are_reported() {
+        let segments = vec![
+            segment("main.obj", 0x3000, 0x10),
+            segment("lib.obj", 0x3008, 0x10),
+            segment("data.obj", 0xFFFF, 2),
+            segment("vectors.obj", 0x0000, 1),
+        ];
+
+        assert_eq!(
+            find_overlaps(&segments),
+            vec![
+                Overlap {
+                    first_file: "main.obj".to_string(),
+                    second_file: "lib.obj".to_string(),
+                    start: 0x3008,
+                    end: 0x300F,
+                },
+                Overlap {
+                    first_file: "data.obj".to_string(),
+                    second_file: "vectors.obj".to_string(),
+                    start: 0x0000,
+                    end: 0x0000,
+                },
+            ]
+        );
+        assert!(matches!(
+            check_overlaps(&segments),
+            Err(LoadError::OverlappingSegments(_))
+        ));
+    }
+
+    #[test]
+    fn entry_file_chooses_the_starting_pc() {
+        let segments = vec![
+            segment("lib.obj", 0x4000, 1),
+            segment("main.obj", 0x3000, 1),
+        ];
+
+        assert_eq!(entry_point(&segments, None).unwrap(), 0x4000);
+        assert_eq!(entry_point(&segments, Some("main.obj")).unwrap(), 0x3000);
+        assert!(matches!(
+            entry_point(&segments, Some("other.obj")),
+            Err(LoadError::UnknownEntryFile(_))
+        ));
+    }
+}
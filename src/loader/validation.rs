@@ -0,0 +1,176 @@
+use std::fmt::Display;
+
+use crate::virtual_machine::lc3_virtual_machine::{IO_PAGE_START, USER_SPACE_START};
+
+use super::{
+    error::LoadError,
+    segment::{Segment, ADDRESS_SPACE_SIZE},
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LoadWarning {
+    TrailingByte(String),
+    NoWords(String),
+    ZeroOrigin(String),
+    ExceedsAddressSpace { file: String, size: usize },
+    WrapsAddressSpace { file: String, end: u16 },
+    OverlapsSystemSpace { file: String, start: u16, end: u16 },
+    OverlapsIoPage { file: String, start: u16, end: u16 },
+}
+
+impl Display for LoadWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LoadWarning::TrailingByte(file) => {
+                write!(
+                    f,
+                    "{file} ends with an odd byte, the incomplete last word is ignored"
+                )
+            }
+            LoadWarning::NoWords(file) => write!(f, "{file} holds only an origin"),
+            LoadWarning::ZeroOrigin(file) => write!(f, "{file} has origin x0000"),
+            LoadWarning::ExceedsAddressSpace { file, size } => write!(
+                f,
+                "{file} holds {size} words, more than the {ADDRESS_SPACE_SIZE} of the address space"
+            ),
+            LoadWarning::WrapsAddressSpace { file, end } => {
+                write!(f, "{file} wraps past xFFFF into x0000-x{end:04X}")
+            }
+            LoadWarning::OverlapsSystemSpace { file, start, end } => write!(
+                f,
+                "{file} loads into system space at x{start:04X}-x{end:04X}"
+            ),
+            LoadWarning::OverlapsIoPage { file, start, end } => {
+                write!(
+                    f,
+                    "{file} loads into the I/O page at x{start:04X}-x{end:04X}"
+                )
+            }
+        }
+    }
+}
+
+/// Operating system images are expected to start at x0000 and fill system space.
+pub fn validate_segment(segment: &Segment, is_operating_system: bool) -> Vec<LoadWarning> {
+    let file = segment.file.clone();
+    let mut warnings = Vec::new();
+    if segment.has_trailing_byte {
+        warnings.push(LoadWarning::TrailingByte(file.clone()));
+    }
+    if segment.words.is_empty() {
+        warnings.push(LoadWarning::NoWords(file.clone()));
+    }
+    if segment.origin == 0 && !is_operating_system {
+        warnings.push(LoadWarning::ZeroOrigin(file.clone()));
+    }
+    if segment.words.len() > ADDRESS_SPACE_SIZE as usize {
+        warnings.push(LoadWarning::ExceedsAddressSpace {
+            file: file.clone(),
+            size: segment.words.len(),
+        });
+    }
+    let address_ranges = segment.address_ranges();
+    if let Some((_, wrapped_end)) = address_ranges.get(1) {
+        warnings.push(LoadWarning::WrapsAddressSpace {
+            file: file.clone(),
+            end: (wrapped_end - 1) as u16,
+        });
+    }
+    for (start, end) in address_ranges {
+        if !is_operating_system && start < USER_SPACE_START as u32 {
+            warnings.push(LoadWarning::OverlapsSystemSpace {
+                file: file.clone(),
+                start: start as u16,
+                end: (end.min(USER_SPACE_START as u32) - 1) as u16,
+            });
+        }
+        if end > IO_PAGE_START as u32 {
+            warnings.push(LoadWarning::OverlapsIoPage {
+                file: file.clone(),
+                start: start.max(IO_PAGE_START as u32) as u16,
+                end: (end - 1) as u16,
+            });
+        }
+    }
+    warnings
+}
+
+/// In strict mode any warning refuses the load, otherwise the warnings are handed back.
+pub fn check_warnings(
+    warnings: Vec<LoadWarning>,
+    strict: bool,
+) -> Result<Vec<LoadWarning>, LoadError> {
+    if strict && !warnings.is_empty() {
+        return Err(LoadError::Rejected(warnings));
+    }
+    Ok(warnings)
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::{check_warnings, validate_segment, LoadWarning};
+    use crate::loader::{error::LoadError, segment::Segment};
+
+    #[test]
+    fn well_formed_program_has_no_warnings() {
+        let segment = Segment::new("main.obj", 0x3000, vec![0xF025]);
+
+        assert!(validate_segment(&segment, false).is_empty());
+    }
+
+    #[test]
+    fn odd_trailing_byte_and_empty_files_are_reported() {
+        let segment = Segment::from_object_bytes("odd.obj", &[0x30, 0x00, 0xF0]).unwrap();
+        assert_eq!(
+            validate_segment(&segment, false),
+            vec![
+                LoadWarning::TrailingByte("odd.obj".to_string()),
+                LoadWarning::NoWords("odd.obj".to_string()),
+            ]
+        );
+
+        assert!(matches!(
+            Segment::from_object_bytes("empty.obj", &[]),
+            Err(LoadError::MissingOrigin(_))
+        ));
+    }
+
+    #[test]
+    fn segments_outside_user_space_are_reported() {
+        let wrapping = Segment::new("wrap.obj", 0xFFFE, vec![0; 4]);
+        assert_eq!(
+            validate_segment(&wrapping, false),
+            vec![
+                LoadWarning::WrapsAddressSpace {
+                    file: "wrap.obj".to_string(),
+                    end: 0x0001
+                },
+                LoadWarning::OverlapsIoPage {
+                    file: "wrap.obj".to_string(),
+                    start: 0xFFFE,
+                    end: 0xFFFF
+                },
+                LoadWarning::OverlapsSystemSpace {
+                    file: "wrap.obj".to_string(),
+                    start: 0x0000,
+                    end: 0x0001
+                },
+            ]
+        );
+
+        let vectors = Segment::new("vectors.obj", 0x0000, vec![0; 2]);
+        assert_eq!(validate_segment(&vectors, true), vec![]);
+        assert_eq!(validate_segment(&vectors, false).len(), 2);
+    }
+
+    #[test]
+    fn strict_mode_turns_warnings_into_errors() {
+        let warnings = vec![LoadWarning::ZeroOrigin("main.obj".to_string())];
+
+        assert_eq!(check_warnings(warnings.clone(), false).unwrap(), warnings);
+        assert!(matches!(
+            check_warnings(warnings, true),
+            Err(LoadError::Rejected(_))
+        ));
+    }
+}
@@ -1,13 +1,30 @@
-use std::io::Error;
+use std::{
+    io::{stdout, Error, Write},
+    path::Path,
+    process::ExitCode,
+    time::Duration,
+};
 
-use lc3_vm::virtual_machine_start::{
-    debug_program_from_file, execute_program_from_file, execute_vm_in_interactive_mode,
+use lc3_vm::{
+    assembler::{disassembler::disassemble_file, lc3_assembler::assemble_file},
+    loader::format::ImageFormat,
+    virtual_machine::lc3_virtual_machine::{RunLimits, RunOutcome},
+    virtual_machine_start::{
+        debug_program_from_file, debug_program_with_gdb, execute_program_from_file,
+        execute_vm_in_interactive_mode, ProgramOptions,
+    },
 };
 
-use clap::{arg, ArgGroup, ArgMatches, Command};
+use clap::{arg, value_parser, ArgAction, ArgGroup, ArgMatches, Command};
 use termios::{tcsetattr, Termios, ECHO, ICANON, TCSANOW};
 
 const STDIN: i32 = 0;
+const VIRTUAL_MACHINE_ERROR_EXIT_CODE: u8 = 1;
+const HOST_ERROR_EXIT_CODE: u8 = 2;
+const ASSEMBLY_ERROR_EXIT_CODE: u8 = 1;
+const INSTRUCTION_LIMIT_EXIT_CODE: u8 = 3;
+const TIMEOUT_EXIT_CODE: u8 = 4;
+const OBJECT_FILE_EXTENSION: &str = "obj";
 
 struct TermiosWrapper {
     termios: Termios,
@@ -20,9 +37,7 @@ impl TermiosWrapper {
         new_termios.c_lflag &= !ICANON & !ECHO;
 
         tcsetattr(STDIN, TCSANOW, &new_termios)?;
-        Ok(Self {
-            termios: new_termios,
-        })
+        Ok(Self { termios })
     }
 }
 
@@ -32,34 +47,181 @@ impl Drop for TermiosWrapper {
     }
 }
 
+/// Rejects negative, infinite and NaN amounts of seconds.
+fn parse_timeout(seconds: &str) -> Result<Duration, String> {
+    let seconds: f64 = seconds.parse().map_err(|error| format!("{error}"))?;
+    Duration::try_from_secs_f64(seconds)
+        .map_err(|_| format!("{seconds} isn't a finite, non-negative amount of seconds"))
+}
+
 pub fn receive_command_line_arguments() -> ArgMatches {
     Command::new("LC3 Virtual Machine")
         .arg(arg!(-i --interactive "interactive console").required(false))
-        .arg(arg!(-f --file <FILE> "file to execute").required(false))
-        .arg(arg!(-d --debug <FILE> "debug file").required(false))
+        .arg(
+            arg!(-f --file <FILE> "files to execute, loaded into the same machine")
+                .num_args(1..)
+                .action(ArgAction::Append)
+                .required(false),
+        )
+        .arg(
+            arg!(-d --debug <FILE> "files to debug, loaded into the same machine")
+                .num_args(1..)
+                .action(ArgAction::Append)
+                .required(false),
+        )
+        .arg(
+            arg!(--gdb <PORT> "with -d, wait for a gdb remote connection on localhost:PORT instead of the debugger")
+                .value_parser(value_parser!(u16))
+                .requires("debug")
+                .required(false),
+        )
+        .arg(
+            arg!(--entry <FILE> "file whose origin is the starting PC, the first file by default")
+                .required(false),
+        )
+        .arg(
+            arg!(--format <FORMAT> "format of the program files: obj, hex (a 4 digit hex word per line) bin (16 0/1 chars per line) or lc3tools (lc3tools object files), detected by default")
+                .value_parser(value_parser!(ImageFormat))
+                .required(false),
+        )
+        .arg(
+            arg!(--"max-instructions" <N> "stop the program after N instructions")
+                .value_parser(value_parser!(u64))
+                .required(false),
+        )
+        .arg(
+            arg!(--timeout <SECS> "stop the program after SECS seconds, also while it waits for input")
+                .value_parser(parse_timeout)
+                .required(false),
+        )
+        .arg(
+            arg!(--strict "refuse to run programs the loader warns about").required(false),
+        )
+        .arg(
+            arg!(--os <OS_FILE> "operating system image to load before the program, traps go through its trap vector table")
+                .required(false),
+        )
+        .arg(
+            arg!(--sandbox <DIR> "directory the program can open files in through the file system traps x30-x33")
+                .required(false),
+        )
         .group(
             ArgGroup::new("run program")
                 .args(["interactive", "file", "debug"])
                 .required(false),
         )
+        .subcommand(
+            Command::new("asm")
+                .about("assemble an LC-3 source file into an object file")
+                .arg(arg!(<FILE> "assembly source file"))
+                .arg(
+                    arg!(-o --output <OUTPUT> "object file to write, FILE with the .obj extension by default")
+                        .required(false),
+                ),
+        )
+        .subcommand(
+            Command::new("disasm")
+                .about("list a program image as LC-3 instructions, with labels from its .sym file")
+                .arg(arg!(<FILE> "object, hex or bin file")),
+        )
         .after_help("Don't use -i, -f or -d at the same time")
         .get_matches()
 }
 
-fn main() -> Result<(), Error> {
+fn run_program(files: &[&str], options: ProgramOptions) -> ExitCode {
+    // stdin isn't always a terminal (e.g. piped input), so raw mode is best effort
+    let _terminal = TermiosWrapper::new().ok();
+    match execute_program_from_file(files, options) {
+        Ok(RunOutcome::Halted) => ExitCode::SUCCESS,
+        Ok(RunOutcome::InstructionLimitReached) => ExitCode::from(INSTRUCTION_LIMIT_EXIT_CODE),
+        Ok(RunOutcome::TimedOut) => ExitCode::from(TIMEOUT_EXIT_CODE),
+        Err(error) => {
+            eprintln!("Error running {}: {error}", files.join(" "));
+            ExitCode::from(VIRTUAL_MACHINE_ERROR_EXIT_CODE)
+        }
+    }
+}
+
+fn assemble_program(args: &ArgMatches) -> ExitCode {
+    let Some(file) = args.get_one::<String>("FILE") else {
+        return ExitCode::from(HOST_ERROR_EXIT_CODE);
+    };
+    let output = match args.get_one::<String>("output") {
+        Some(output) => Path::new(output).to_path_buf(),
+        None => Path::new(file).with_extension(OBJECT_FILE_EXTENSION),
+    };
+    match assemble_file(file, &output) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("Error assembling {file}: {error}");
+            ExitCode::from(ASSEMBLY_ERROR_EXIT_CODE)
+        }
+    }
+}
+
+fn disassemble_program(args: &ArgMatches) -> ExitCode {
+    let Some(file) = args.get_one::<String>("FILE") else {
+        return ExitCode::from(HOST_ERROR_EXIT_CODE);
+    };
+    match disassemble_file(file) {
+        Ok(listing) => {
+            let mut stdout = stdout().lock();
+            // stop quietly when the reader goes away, e.g. piping into `head`
+            for line in listing {
+                if writeln!(stdout, "{line}").is_err() {
+                    break;
+                }
+            }
+            ExitCode::SUCCESS
+        }
+        Err(error) => {
+            eprintln!("Error disassembling {file}: {error}");
+            ExitCode::from(HOST_ERROR_EXIT_CODE)
+        }
+    }
+}
+
+fn main() -> ExitCode {
     let args = receive_command_line_arguments();
-    if let Some(file) = args.get_one::<String>("file") {
-        TermiosWrapper::new()?;
-        execute_program_from_file(file)?;
+    match args.subcommand() {
+        Some(("asm", asm_args)) => return assemble_program(asm_args),
+        Some(("disasm", disasm_args)) => return disassemble_program(disasm_args),
+        _ => {}
+    }
+    let options = ProgramOptions {
+        operating_system_file: args.get_one::<String>("os").map(String::as_str),
+        sandbox_directory: args.get_one::<String>("sandbox").map(String::as_str),
+        entry_file: args.get_one::<String>("entry").map(String::as_str),
+        format: args.get_one::<ImageFormat>("format").copied(),
+        strict: args.get_flag("strict"),
+        limits: RunLimits {
+            max_instructions: args.get_one::<u64>("max-instructions").copied(),
+            timeout: args.get_one::<Duration>("timeout").copied(),
+        },
+    };
+    if let Some(files) = args.get_many::<String>("file") {
+        let files: Vec<&str> = files.map(String::as_str).collect();
+        return run_program(&files, options);
     }
 
     if args.get_flag("interactive") {
-        execute_vm_in_interactive_mode()?;
+        if let Err(error) = execute_vm_in_interactive_mode() {
+            eprintln!("Error in interactive console: {error}");
+            return ExitCode::from(HOST_ERROR_EXIT_CODE);
+        }
     }
 
-    if let Some(file) = args.get_one::<String>("debug") {
-        debug_program_from_file(file)?;
+    if let Some(files) = args.get_many::<String>("debug") {
+        let files: Vec<&str> = files.map(String::as_str).collect();
+        let result = match args.get_one::<u16>("gdb") {
+            Some(port) => debug_program_with_gdb(&files, options, *port),
+            None => debug_program_from_file(&files, options),
+        };
+        if let Err(error) = result {
+            eprintln!("Error debugging {}: {error}", files.join(" "));
+            return ExitCode::from(HOST_ERROR_EXIT_CODE);
+        }
     }
 
-    Ok(())
+    ExitCode::SUCCESS
 }
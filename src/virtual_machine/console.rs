@@ -0,0 +1,264 @@
+use std::{
+    collections::VecDeque,
+    fs::File,
+    io::{stdin, stdout, BufReader, BufWriter, Error, ErrorKind, Read, Write},
+    path::Path,
+    sync::{
+        mpsc::{self, Receiver, RecvTimeoutError, TryRecvError},
+        Arc, Mutex, MutexGuard, OnceLock,
+    },
+    thread,
+    time::Instant,
+};
+
+pub trait Console {
+    fn read_byte(&mut self) -> Result<u8, Error>;
+
+    fn poll_byte(&mut self) -> Result<Option<u8>, Error>;
+
+    /// Like `read_byte`, but gives up with `None` once `deadline` has passed.
+    /// Consoles whose reads don't wait on anyone just read.
+    fn read_byte_until(&mut self, _deadline: Instant) -> Result<Option<u8>, Error> {
+        self.read_byte().map(Some)
+    }
+
+    fn write_byte(&mut self, byte: u8) -> Result<(), Error>;
+
+    fn flush(&mut self) -> Result<(), Error>;
+
+    fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), Error> {
+        for byte in bytes {
+            self.write_byte(*byte)?;
+        }
+        Ok(())
+    }
+}
+
+pub struct StandardConsole;
+
+impl StandardConsole {
+    fn standard_input() -> &'static Mutex<Receiver<u8>> {
+        static STANDARD_INPUT: OnceLock<Mutex<Receiver<u8>>> = OnceLock::new();
+        STANDARD_INPUT.get_or_init(|| {
+            let (sender, receiver) = mpsc::channel();
+            thread::spawn(move || {
+                let mut buffer = [0; 1];
+                while stdin().read_exact(&mut buffer).is_ok() {
+                    if sender.send(buffer[0]).is_err() {
+                        break;
+                    }
+                }
+            });
+            Mutex::new(receiver)
+        })
+    }
+
+    /// Reads a line through the same reader thread as the VM's input, so the two don't race
+    /// for stdin. Returns `None` once stdin is closed and no partial line is left.
+    pub fn read_line(&mut self) -> Result<Option<String>, Error> {
+        let mut line = Vec::new();
+        let standard_input = lock(Self::standard_input());
+        loop {
+            match standard_input.recv() {
+                Ok(b'\n') => break,
+                Ok(byte) => line.push(byte),
+                Err(_) if line.is_empty() => return Ok(None),
+                Err(_) => break,
+            }
+        }
+        if line.last() == Some(&b'\r') {
+            line.pop();
+        }
+        String::from_utf8(line)
+            .map(Some)
+            .map_err(|error| Error::new(ErrorKind::InvalidData, error))
+    }
+}
+
+impl Console for StandardConsole {
+    fn read_byte(&mut self) -> Result<u8, Error> {
+        lock(Self::standard_input())
+            .recv()
+            .map_err(|_| Error::new(ErrorKind::UnexpectedEof, "stdin is closed"))
+    }
+
+    fn poll_byte(&mut self) -> Result<Option<u8>, Error> {
+        match lock(Self::standard_input()).try_recv() {
+            Ok(byte) => Ok(Some(byte)),
+            Err(TryRecvError::Empty | TryRecvError::Disconnected) => Ok(None),
+        }
+    }
+
+    fn read_byte_until(&mut self, deadline: Instant) -> Result<Option<u8>, Error> {
+        let timeout = deadline.saturating_duration_since(Instant::now());
+        match lock(Self::standard_input()).recv_timeout(timeout) {
+            Ok(byte) => Ok(Some(byte)),
+            Err(RecvTimeoutError::Timeout) => Ok(None),
+            Err(RecvTimeoutError::Disconnected) => {
+                Err(Error::new(ErrorKind::UnexpectedEof, "stdin is closed"))
+            }
+        }
+    }
+
+    fn write_byte(&mut self, byte: u8) -> Result<(), Error> {
+        stdout().write_all(&[byte])
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        stdout().flush()
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct BufferConsole {
+    input: Arc<Mutex<VecDeque<u8>>>,
+    output: Arc<Mutex<Vec<u8>>>,
+}
+
+impl BufferConsole {
+    pub fn new(input: &[u8]) -> Self {
+        let console = Self::default();
+        console.push_input(input);
+        console
+    }
+
+    pub fn push_input(&self, bytes: &[u8]) {
+        lock(&self.input).extend(bytes);
+    }
+
+    pub fn output(&self) -> Vec<u8> {
+        lock(&self.output).clone()
+    }
+
+    pub fn output_string(&self) -> String {
+        String::from_utf8_lossy(&self.output()).into_owned()
+    }
+}
+
+impl Console for BufferConsole {
+    fn read_byte(&mut self) -> Result<u8, Error> {
+        self.poll_byte()?
+            .ok_or_else(|| Error::new(ErrorKind::UnexpectedEof, "console input is exhausted"))
+    }
+
+    fn poll_byte(&mut self) -> Result<Option<u8>, Error> {
+        Ok(lock(&self.input).pop_front())
+    }
+
+    fn write_byte(&mut self, byte: u8) -> Result<(), Error> {
+        lock(&self.output).push(byte);
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+pub struct FileConsole {
+    input: BufReader<File>,
+    output: BufWriter<File>,
+}
+
+impl FileConsole {
+    pub fn open(
+        input_path: impl AsRef<Path>,
+        output_path: impl AsRef<Path>,
+    ) -> Result<Self, Error> {
+        Ok(Self {
+            input: BufReader::new(File::open(input_path)?),
+            output: BufWriter::new(File::create(output_path)?),
+        })
+    }
+}
+
+impl Console for FileConsole {
+    fn read_byte(&mut self) -> Result<u8, Error> {
+        let mut buffer = [0; 1];
+        self.input.read_exact(&mut buffer)?;
+        Ok(buffer[0])
+    }
+
+    fn poll_byte(&mut self) -> Result<Option<u8>, Error> {
+        match self.read_byte() {
+            Ok(byte) => Ok(Some(byte)),
+            Err(error) if error.kind() == ErrorKind::UnexpectedEof => Ok(None),
+            Err(error) => Err(error),
+        }
+    }
+
+    fn write_byte(&mut self, byte: u8) -> Result<(), Error> {
+        self.output.write_all(&[byte])
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        self.output.flush()
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+#[cfg(test)]
+pub mod test {
+    use std::{env, fs, io::ErrorKind, path::PathBuf, process};
+
+    use super::{BufferConsole, Console, FileConsole};
+    use crate::virtual_machine::lc3_virtual_machine::LC3VirtualMachine;
+
+    const GETC: u16 = 0xF020;
+    const OUT: u16 = 0xF021;
+    const HALT: u16 = 0xF025;
+
+    fn console_files(name: &str, input: &[u8]) -> (PathBuf, PathBuf) {
+        let directory = env::temp_dir();
+        let input_path = directory.join(format!("lc3_vm_console_{}_{name}.in", process::id()));
+        let output_path = directory.join(format!("lc3_vm_console_{}_{name}.out", process::id()));
+        fs::write(&input_path, input).unwrap();
+        (input_path, output_path)
+    }
+
+    #[test]
+    fn file_console_feeds_a_program_and_captures_its_output() {
+        let (input_path, output_path) = console_files("echo", b"hi");
+        let console = FileConsole::open(&input_path, &output_path).unwrap();
+        let mut virtual_machine = LC3VirtualMachine::with_console(0x3000, console);
+        for (offset, instruction) in [GETC, OUT, GETC, OUT, HALT].iter().enumerate() {
+            virtual_machine
+                .memory_write(0x3000 + offset as u16, *instruction)
+                .unwrap();
+        }
+        virtual_machine.run().unwrap();
+
+        assert_eq!(fs::read(&output_path).unwrap(), b"hi");
+        fs::remove_file(input_path).unwrap();
+        fs::remove_file(output_path).unwrap();
+    }
+
+    #[test]
+    fn reading_past_the_end_of_input_fails_and_polling_finds_nothing() {
+        let (input_path, output_path) = console_files("eof", b"a");
+        let mut file_console = FileConsole::open(&input_path, &output_path).unwrap();
+        let mut buffer_console = BufferConsole::new(b"a");
+
+        assert_eq!(file_console.read_byte().unwrap(), b'a');
+        assert_eq!(file_console.poll_byte().unwrap(), None);
+        assert_eq!(
+            file_console.read_byte().unwrap_err().kind(),
+            ErrorKind::UnexpectedEof
+        );
+        assert_eq!(buffer_console.poll_byte().unwrap(), Some(b'a'));
+        assert_eq!(buffer_console.poll_byte().unwrap(), None);
+        assert_eq!(
+            buffer_console.read_byte().unwrap_err().kind(),
+            ErrorKind::UnexpectedEof
+        );
+        buffer_console.push_input(b"b");
+        assert_eq!(buffer_console.read_byte().unwrap(), b'b');
+        fs::remove_file(input_path).unwrap();
+        fs::remove_file(output_path).unwrap();
+    }
+}
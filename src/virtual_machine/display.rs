@@ -0,0 +1,60 @@
+use std::ops::RangeInclusive;
+
+use super::{
+    console::Console,
+    error::VmError,
+    memory_bus::{Device, MemoryMappedRegister},
+};
+
+const DISPLAY_READY_BIT: u16 = 1 << 15;
+
+#[derive(Default)]
+pub struct Display {
+    data: u16,
+}
+
+impl Device for Display {
+    fn address_range(&self) -> RangeInclusive<u16> {
+        MemoryMappedRegister::DisplayStatus as u16..=MemoryMappedRegister::DisplayData as u16
+    }
+
+    fn read(&mut self, memory_address: u16, _console: &mut dyn Console) -> Result<u16, VmError> {
+        if memory_address == MemoryMappedRegister::DisplayStatus as u16 {
+            return Ok(DISPLAY_READY_BIT);
+        }
+        if memory_address == MemoryMappedRegister::DisplayData as u16 {
+            return Ok(self.data);
+        }
+        Ok(0)
+    }
+
+    fn write(
+        &mut self,
+        memory_address: u16,
+        value_to_write: u16,
+        console: &mut dyn Console,
+    ) -> Result<(), VmError> {
+        if memory_address == MemoryMappedRegister::DisplayData as u16 {
+            self.data = value_to_write;
+            console.write_byte(value_to_write as u8)?;
+            console.flush()?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use crate::virtual_machine::{console::BufferConsole, lc3_virtual_machine::LC3VirtualMachine};
+
+    #[test]
+    fn writing_display_data_prints_to_the_console() {
+        let console = BufferConsole::default();
+        let mut virtual_machine = LC3VirtualMachine::with_console(0x3000, console.clone());
+
+        assert_eq!(virtual_machine.memory_read(0xFE04).unwrap(), 0x8000);
+        virtual_machine.memory_write(0xFE06, b'!' as u16).unwrap();
+        assert_eq!(console.output_string(), "!");
+        assert_eq!(virtual_machine.memory_read(0xFE06).unwrap(), b'!' as u16);
+    }
+}
@@ -0,0 +1,76 @@
+use std::{fmt::Display, io::Error};
+
+use crate::loader::error::LoadError;
+
+#[derive(Debug)]
+pub enum VmError {
+    IllegalOpcode(u16),
+    UnknownTrap(u16),
+    InvalidRegister(u16),
+    PrivilegeModeViolation,
+    AccessControlViolation(u16),
+    Io(Error),
+    Load(LoadError),
+    InputTimedOut,
+}
+
+const PRIVILEGE_MODE_VIOLATION_VECTOR: u16 = 0x00;
+const ILLEGAL_OPCODE_VECTOR: u16 = 0x01;
+const ACCESS_CONTROL_VIOLATION_VECTOR: u16 = 0x02;
+
+impl VmError {
+    pub fn exception_vector(&self) -> Option<u16> {
+        match self {
+            VmError::PrivilegeModeViolation => Some(PRIVILEGE_MODE_VIOLATION_VECTOR),
+            VmError::IllegalOpcode(_) => Some(ILLEGAL_OPCODE_VECTOR),
+            VmError::AccessControlViolation(_) => Some(ACCESS_CONTROL_VIOLATION_VECTOR),
+            _ => None,
+        }
+    }
+}
+
+impl Display for VmError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VmError::IllegalOpcode(instruction) => {
+                write!(f, "illegal opcode in instruction {instruction:#018b}")
+            }
+            VmError::UnknownTrap(trap_vector) => {
+                write!(f, "unknown trap vector {trap_vector:#04x}")
+            }
+            VmError::InvalidRegister(register) => write!(f, "invalid register index {register}"),
+            VmError::PrivilegeModeViolation => {
+                write!(f, "privilege mode violation: RTI executed in user mode")
+            }
+            VmError::AccessControlViolation(memory_address) => write!(
+                f,
+                "access control violation: user mode access to {memory_address:#06x}"
+            ),
+            VmError::Io(error) => write!(f, "I/O failure: {error}"),
+            VmError::Load(error) => write!(f, "couldn't load the program: {error}"),
+            VmError::InputTimedOut => write!(f, "timed out waiting for input"),
+        }
+    }
+}
+
+impl std::error::Error for VmError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            VmError::Io(error) => Some(error),
+            VmError::Load(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<Error> for VmError {
+    fn from(error: Error) -> Self {
+        VmError::Io(error)
+    }
+}
+
+impl From<LoadError> for VmError {
+    fn from(error: LoadError) -> Self {
+        VmError::Load(error)
+    }
+}
@@ -0,0 +1,375 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    fs::{self, File, OpenOptions},
+    io::{Error, ErrorKind, Read, Write},
+    path::{Component, Path, PathBuf},
+    rc::Rc,
+};
+
+use super::{
+    error::VmError,
+    lc3_virtual_machine::{LC3VirtualMachine, StepOutcome},
+    register::Register,
+};
+
+pub const OPEN_TRAP_VECTOR: u8 = 0x30;
+pub const READ_TRAP_VECTOR: u8 = 0x31;
+pub const WRITE_TRAP_VECTOR: u8 = 0x32;
+pub const CLOSE_TRAP_VECTOR: u8 = 0x33;
+pub const FILE_SYSTEM_ERROR: u16 = 0xFFFF;
+
+const READ_MODE: u16 = 0;
+const WRITE_MODE: u16 = 1;
+const APPEND_MODE: u16 = 2;
+
+pub struct SandboxedFileSystem {
+    root: PathBuf,
+    open_files: HashMap<u16, File>,
+    next_handle: u16,
+}
+
+impl SandboxedFileSystem {
+    pub fn new(root: impl AsRef<Path>) -> Result<Self, Error> {
+        Ok(Self {
+            root: fs::canonicalize(root)?,
+            open_files: HashMap::new(),
+            next_handle: 0,
+        })
+    }
+
+    pub fn install(self, virtual_machine: &mut LC3VirtualMachine) {
+        let file_system = Rc::new(RefCell::new(self));
+
+        let open_file_system = Rc::clone(&file_system);
+        virtual_machine.register_trap(OPEN_TRAP_VECTOR, move |virtual_machine| {
+            open_file_system.borrow_mut().open(virtual_machine)
+        });
+        let read_file_system = Rc::clone(&file_system);
+        virtual_machine.register_trap(READ_TRAP_VECTOR, move |virtual_machine| {
+            read_file_system.borrow_mut().read(virtual_machine)
+        });
+        let write_file_system = Rc::clone(&file_system);
+        virtual_machine.register_trap(WRITE_TRAP_VECTOR, move |virtual_machine| {
+            write_file_system.borrow_mut().write(virtual_machine)
+        });
+        virtual_machine.register_trap(CLOSE_TRAP_VECTOR, move |virtual_machine| {
+            file_system.borrow_mut().close(virtual_machine)
+        });
+    }
+
+    fn resolve(&self, file_name: &str) -> Result<PathBuf, Error> {
+        let relative_path = Path::new(file_name);
+        let is_plain_relative_path = !file_name.is_empty()
+            && relative_path
+                .components()
+                .all(|component| matches!(component, Component::Normal(_)));
+        if !is_plain_relative_path {
+            return Err(Error::new(
+                ErrorKind::PermissionDenied,
+                "path escapes the sandbox",
+            ));
+        }
+        let path = self.root.join(relative_path);
+        // existing files are followed to their target, dangling symlinks fail to canonicalize
+        let resolved_path = match fs::symlink_metadata(&path) {
+            Ok(_) => fs::canonicalize(&path)?,
+            Err(error) if error.kind() == ErrorKind::NotFound => {
+                let parent = path.parent().map(fs::canonicalize).transpose()?;
+                match (parent, path.file_name()) {
+                    (Some(parent), Some(file_name)) => parent.join(file_name),
+                    _ => return Err(error),
+                }
+            }
+            Err(error) => return Err(error),
+        };
+        if !resolved_path.starts_with(&self.root) {
+            return Err(Error::new(
+                ErrorKind::PermissionDenied,
+                "path escapes the sandbox",
+            ));
+        }
+        Ok(resolved_path)
+    }
+
+    fn open_file(&mut self, file_name: &str, mode: u16) -> Result<u16, Error> {
+        // checked first, creating the file would already truncate it
+        if self.open_files.len() >= FILE_SYSTEM_ERROR as usize {
+            return Err(Error::new(ErrorKind::OutOfMemory, "too many open files"));
+        }
+        let path = self.resolve(file_name)?;
+        let file = match mode {
+            READ_MODE => File::open(path)?,
+            WRITE_MODE => File::create(path)?,
+            APPEND_MODE => OpenOptions::new().append(true).create(true).open(path)?,
+            _ => return Err(Error::new(ErrorKind::InvalidInput, "unknown open mode")),
+        };
+        while self.next_handle == FILE_SYSTEM_ERROR
+            || self.open_files.contains_key(&self.next_handle)
+        {
+            self.next_handle = self.next_handle.wrapping_add(1);
+        }
+        let handle = self.next_handle;
+        self.open_files.insert(handle, file);
+        self.next_handle = self.next_handle.wrapping_add(1);
+        Ok(handle)
+    }
+
+    fn open(&mut self, virtual_machine: &mut LC3VirtualMachine) -> Result<StepOutcome, VmError> {
+        let mut file_name = String::new();
+        let mut read_index = virtual_machine.read_register(Register::R0);
+        let mut char = virtual_machine.memory_read(read_index)?;
+        while char != 0 {
+            file_name.push((char as u8) as char);
+            read_index = read_index.wrapping_add(1);
+            char = virtual_machine.memory_read(read_index)?;
+        }
+        let mode = virtual_machine.read_register(Register::R1);
+        let handle = self
+            .open_file(&file_name, mode)
+            .unwrap_or(FILE_SYSTEM_ERROR);
+        virtual_machine.update_register(Register::R0, handle);
+        Ok(StepOutcome::Continue)
+    }
+
+    fn read(&mut self, virtual_machine: &mut LC3VirtualMachine) -> Result<StepOutcome, VmError> {
+        let handle = virtual_machine.read_register(Register::R0);
+        let buffer_address = virtual_machine.read_register(Register::R1);
+        let amount_of_words = virtual_machine.read_register(Register::R2);
+        if !is_buffer_accessible(virtual_machine, buffer_address, amount_of_words) {
+            virtual_machine.update_register(Register::R0, FILE_SYSTEM_ERROR);
+            return Ok(StepOutcome::Continue);
+        }
+        let mut buffer = vec![0; amount_of_words as usize];
+        let amount_read = match self.open_files.get_mut(&handle) {
+            Some(file) => file.read(&mut buffer).ok(),
+            None => None,
+        };
+        let Some(amount_read) = amount_read else {
+            virtual_machine.update_register(Register::R0, FILE_SYSTEM_ERROR);
+            return Ok(StepOutcome::Continue);
+        };
+        for (offset, byte) in buffer[..amount_read].iter().enumerate() {
+            virtual_machine
+                .memory_write(buffer_address.wrapping_add(offset as u16), *byte as u16)?;
+        }
+        virtual_machine.update_register(Register::R0, amount_read as u16);
+        Ok(StepOutcome::Continue)
+    }
+
+    fn write(&mut self, virtual_machine: &mut LC3VirtualMachine) -> Result<StepOutcome, VmError> {
+        let handle = virtual_machine.read_register(Register::R0);
+        let buffer_address = virtual_machine.read_register(Register::R1);
+        let amount_of_words = virtual_machine.read_register(Register::R2);
+        if !is_buffer_accessible(virtual_machine, buffer_address, amount_of_words) {
+            virtual_machine.update_register(Register::R0, FILE_SYSTEM_ERROR);
+            return Ok(StepOutcome::Continue);
+        }
+        let mut buffer = Vec::with_capacity(amount_of_words as usize);
+        for offset in 0..amount_of_words {
+            buffer.push(virtual_machine.memory_read(buffer_address.wrapping_add(offset))? as u8);
+        }
+        let result = match self.open_files.get_mut(&handle) {
+            Some(file) => file
+                .write_all(&buffer)
+                .map_or(FILE_SYSTEM_ERROR, |_| amount_of_words),
+            None => FILE_SYSTEM_ERROR,
+        };
+        virtual_machine.update_register(Register::R0, result);
+        Ok(StepOutcome::Continue)
+    }
+
+    fn close(&mut self, virtual_machine: &mut LC3VirtualMachine) -> Result<StepOutcome, VmError> {
+        let handle = virtual_machine.read_register(Register::R0);
+        let result = match self.open_files.remove(&handle) {
+            Some(file) => file.sync_all().map_or(FILE_SYSTEM_ERROR, |_| 0),
+            None => FILE_SYSTEM_ERROR,
+        };
+        virtual_machine.update_register(Register::R0, result);
+        Ok(StepOutcome::Continue)
+    }
+}
+
+/// Traps run with the caller's privilege, so a user program can't reach system memory through them.
+fn is_buffer_accessible(
+    virtual_machine: &LC3VirtualMachine,
+    buffer_address: u16,
+    amount_of_words: u16,
+) -> bool {
+    (0..amount_of_words).all(|offset| {
+        virtual_machine
+            .check_access_control(buffer_address.wrapping_add(offset))
+            .is_ok()
+    })
+}
+
+#[cfg(test)]
+pub mod test {
+    use std::{env, fs, os::unix::fs::symlink, path::PathBuf, process};
+
+    use super::{SandboxedFileSystem, FILE_SYSTEM_ERROR};
+    use crate::virtual_machine::{
+        console::BufferConsole, lc3_virtual_machine::LC3VirtualMachine, register::Register,
+    };
+
+    const OPEN: u16 = 0xF030;
+    const READ: u16 = 0xF031;
+    const WRITE: u16 = 0xF032;
+    const CLOSE: u16 = 0xF033;
+
+    fn sandbox(name: &str) -> PathBuf {
+        let directory = env::temp_dir().join(format!("lc3_vm_sandbox_{}_{name}", process::id()));
+        let _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(&directory).unwrap();
+        directory
+    }
+
+    fn virtual_machine_with_sandbox(directory: &PathBuf) -> LC3VirtualMachine {
+        let mut virtual_machine = LC3VirtualMachine::with_console(0x3000, BufferConsole::default());
+        SandboxedFileSystem::new(directory)
+            .unwrap()
+            .install(&mut virtual_machine);
+        virtual_machine
+    }
+
+    fn write_string(virtual_machine: &mut LC3VirtualMachine, memory_address: u16, string: &str) {
+        for (offset, char) in string.bytes().chain([0]).enumerate() {
+            virtual_machine
+                .memory_write(memory_address + offset as u16, char as u16)
+                .unwrap();
+        }
+    }
+
+    fn open(virtual_machine: &mut LC3VirtualMachine, file_name: &str, mode: u16) -> u16 {
+        write_string(virtual_machine, 0x4000, file_name);
+        virtual_machine.update_register(Register::R0, 0x4000);
+        virtual_machine.update_register(Register::R1, mode);
+        virtual_machine.decode_instruction(OPEN).unwrap();
+        virtual_machine.read_register(Register::R0)
+    }
+
+    #[test]
+    fn can_write_and_read_back_a_file() {
+        let directory = sandbox("round_trip");
+        let mut virtual_machine = virtual_machine_with_sandbox(&directory);
+
+        let handle = open(&mut virtual_machine, "notes.txt", 1);
+        assert_ne!(handle, FILE_SYSTEM_ERROR);
+        write_string(&mut virtual_machine, 0x5000, "lc3");
+        virtual_machine.update_register(Register::R0, handle);
+        virtual_machine.update_register(Register::R1, 0x5000);
+        virtual_machine.update_register(Register::R2, 3);
+        virtual_machine.decode_instruction(WRITE).unwrap();
+        assert_eq!(virtual_machine.read_register(Register::R0), 3);
+        virtual_machine.update_register(Register::R0, handle);
+        virtual_machine.decode_instruction(CLOSE).unwrap();
+        assert_eq!(virtual_machine.read_register(Register::R0), 0);
+        assert_eq!(
+            fs::read_to_string(directory.join("notes.txt")).unwrap(),
+            "lc3"
+        );
+
+        let handle = open(&mut virtual_machine, "notes.txt", 0);
+        virtual_machine.update_register(Register::R0, handle);
+        virtual_machine.update_register(Register::R1, 0x6000);
+        virtual_machine.update_register(Register::R2, 10);
+        virtual_machine.decode_instruction(READ).unwrap();
+        assert_eq!(virtual_machine.read_register(Register::R0), 3);
+        assert_eq!(virtual_machine.memory_read(0x6000).unwrap(), b'l' as u16);
+        assert_eq!(virtual_machine.memory_read(0x6002).unwrap(), b'3' as u16);
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn paths_outside_the_sandbox_fail_to_open() {
+        let directory = sandbox("escape");
+        let mut virtual_machine = virtual_machine_with_sandbox(&directory);
+
+        assert_eq!(
+            open(&mut virtual_machine, "../escape.txt", 1),
+            FILE_SYSTEM_ERROR
+        );
+        assert_eq!(
+            open(&mut virtual_machine, "/etc/passwd", 0),
+            FILE_SYSTEM_ERROR
+        );
+        assert_eq!(
+            open(&mut virtual_machine, "missing.txt", 0),
+            FILE_SYSTEM_ERROR
+        );
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn symlinks_out_of_the_sandbox_fail_to_open() {
+        let directory = sandbox("symlink");
+        let outside = sandbox("symlink_target");
+        fs::write(outside.join("secret.txt"), "secret").unwrap();
+        symlink(outside.join("secret.txt"), directory.join("link.txt")).unwrap();
+        symlink(outside.join("new.txt"), directory.join("dangling.txt")).unwrap();
+        let mut virtual_machine = virtual_machine_with_sandbox(&directory);
+
+        assert_eq!(open(&mut virtual_machine, "link.txt", 0), FILE_SYSTEM_ERROR);
+        assert_eq!(open(&mut virtual_machine, "link.txt", 1), FILE_SYSTEM_ERROR);
+        assert_eq!(
+            open(&mut virtual_machine, "dangling.txt", 1),
+            FILE_SYSTEM_ERROR
+        );
+        assert_eq!(
+            fs::read_to_string(outside.join("secret.txt")).unwrap(),
+            "secret"
+        );
+        assert!(!outside.join("new.txt").exists());
+
+        fs::remove_dir_all(directory).unwrap();
+        fs::remove_dir_all(outside).unwrap();
+    }
+
+    #[test]
+    fn user_programs_cannot_move_system_memory_through_files() {
+        let directory = sandbox("privilege");
+        fs::write(directory.join("payload.txt"), "xx").unwrap();
+        let mut virtual_machine = virtual_machine_with_sandbox(&directory);
+        let read_handle = open(&mut virtual_machine, "payload.txt", 0);
+        let write_handle = open(&mut virtual_machine, "dump.txt", 1);
+        virtual_machine.enter_user_mode(0x3000);
+
+        virtual_machine.update_register(Register::R0, read_handle);
+        virtual_machine.update_register(Register::R1, 0x2FFF);
+        virtual_machine.update_register(Register::R2, 2);
+        virtual_machine.decode_instruction(READ).unwrap();
+        assert_eq!(
+            virtual_machine.read_register(Register::R0),
+            FILE_SYSTEM_ERROR
+        );
+        assert_eq!(virtual_machine.memory_read(0x2FFF).unwrap(), 0);
+        virtual_machine.update_register(Register::R0, write_handle);
+        virtual_machine.update_register(Register::R1, 0x0000);
+        virtual_machine.update_register(Register::R2, 0x100);
+        virtual_machine.decode_instruction(WRITE).unwrap();
+        assert_eq!(
+            virtual_machine.read_register(Register::R0),
+            FILE_SYSTEM_ERROR
+        );
+        assert_eq!(fs::read(directory.join("dump.txt")).unwrap(), b"");
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn unknown_handles_return_an_error() {
+        let directory = sandbox("handles");
+        let mut virtual_machine = virtual_machine_with_sandbox(&directory);
+        virtual_machine.update_register(Register::R0, 7);
+        virtual_machine.decode_instruction(CLOSE).unwrap();
+
+        assert_eq!(
+            virtual_machine.read_register(Register::R0),
+            FILE_SYSTEM_ERROR
+        );
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+}
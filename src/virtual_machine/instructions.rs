@@ -1,4 +1,9 @@
-use super::{lc3_virtual_machine::LC3VirtualMachine, register::Register, trap::Trap};
+use super::{
+    error::VmError,
+    lc3_virtual_machine::{LC3VirtualMachine, StepOutcome},
+    register::{PrivilegeMode, Register},
+    trap::Trap,
+};
 pub const BR: u16 = 0b0000;
 pub const ADD: u16 = 0b0001;
 pub const LD: u16 = 0b0010;
@@ -49,31 +54,33 @@ pub fn load(
     virtual_machine: &mut LC3VirtualMachine,
     destination_register: Register,
     program_counter_offset: u16,
-) {
-    let new_register_value = virtual_machine.memory_read(
+) -> Result<(), VmError> {
+    let new_register_value = virtual_machine.checked_memory_read(
         virtual_machine
             .read_register(Register::ProgramCounter)
             .wrapping_add(program_counter_offset),
-    );
+    )?;
 
     virtual_machine.update_register(destination_register, new_register_value);
     virtual_machine.update_flags(destination_register);
+    Ok(())
 }
 
 pub fn load_indirect(
     virtual_machine: &mut LC3VirtualMachine,
     destination_register: Register,
     program_counter_offset: u16,
-) {
-    let memory_address = virtual_machine.memory_read(
+) -> Result<(), VmError> {
+    let memory_address = virtual_machine.checked_memory_read(
         virtual_machine
             .read_register(Register::ProgramCounter)
             .wrapping_add(program_counter_offset),
-    );
-    let new_register_value = virtual_machine.memory_read(memory_address);
+    )?;
+    let new_register_value = virtual_machine.checked_memory_read(memory_address)?;
     virtual_machine.update_register(destination_register, new_register_value);
 
     virtual_machine.update_flags(destination_register);
+    Ok(())
 }
 
 pub fn not(
@@ -121,13 +128,29 @@ pub fn and_inmediate(
     virtual_machine.update_register(destination_register, new_register_value);
 }
 
-pub fn trap_instruction(virtual_machine: &mut LC3VirtualMachine, trap: Trap) {
+pub fn trap_instruction(
+    virtual_machine: &mut LC3VirtualMachine,
+    trap: Trap,
+) -> Result<StepOutcome, VmError> {
     virtual_machine.update_register(
         Register::R7,
         virtual_machine.read_register(Register::ProgramCounter),
     );
 
-    trap.execute_trap(virtual_machine);
+    trap.execute_trap(virtual_machine)
+}
+
+pub fn trap_through_vector_table(
+    virtual_machine: &mut LC3VirtualMachine,
+    trap_vector: u16,
+) -> Result<(), VmError> {
+    virtual_machine.update_register(
+        Register::R7,
+        virtual_machine.read_register(Register::ProgramCounter),
+    );
+
+    let service_routine_address = virtual_machine.memory_read(trap_vector)?;
+    virtual_machine.enter_service_routine(service_routine_address)
 }
 
 pub fn load_base_offset(
@@ -135,12 +158,14 @@ pub fn load_base_offset(
     destination_register: Register,
     base_register: Register,
     offset: u16,
-) {
+) -> Result<(), VmError> {
     let register_value = virtual_machine.read_register(base_register);
-    let new_register_value = virtual_machine.memory_read(register_value.wrapping_add(offset));
+    let new_register_value =
+        virtual_machine.checked_memory_read(register_value.wrapping_add(offset))?;
     virtual_machine.update_register(destination_register, new_register_value);
 
     virtual_machine.update_flags(destination_register);
+    Ok(())
 }
 
 pub fn load_effective_address(
@@ -159,18 +184,19 @@ pub fn load_effective_address(
 pub fn jump(virtual_machine: &mut LC3VirtualMachine, base_register: Register) {
     let new_register_value = virtual_machine.read_register(base_register);
     virtual_machine.update_register(Register::ProgramCounter, new_register_value);
+    if matches!(base_register, Register::R7) {
+        virtual_machine.return_from_subroutine(new_register_value);
+    }
 }
 
 pub fn jump_to_subroutine(virtual_machine: &mut LC3VirtualMachine, base_register: Register) {
-    virtual_machine.update_register(
-        Register::R7,
-        virtual_machine.read_register(Register::ProgramCounter),
-    );
+    // Read first, JSRR R7 jumps to the old R7 and not to the return address.
+    let subroutine_address = virtual_machine.read_register(base_register);
+    let return_address = virtual_machine.read_register(Register::ProgramCounter);
+    virtual_machine.update_register(Register::R7, return_address);
 
-    virtual_machine.update_register(
-        Register::ProgramCounter,
-        virtual_machine.read_register(base_register),
-    );
+    virtual_machine.update_register(Register::ProgramCounter, subroutine_address);
+    virtual_machine.enter_subroutine(return_address.wrapping_sub(1), subroutine_address);
 }
 
 pub fn jump_to_subroutine_with_offset(
@@ -187,36 +213,38 @@ pub fn jump_to_subroutine_with_offset(
         .wrapping_add(program_counter_offset);
 
     virtual_machine.update_register(Register::ProgramCounter, new_register_value);
+    virtual_machine.enter_subroutine(
+        virtual_machine.read_register(Register::R7).wrapping_sub(1),
+        new_register_value,
+    );
 }
 
 pub fn store(
     virtual_machine: &mut LC3VirtualMachine,
     source_register: Register,
     program_counter_offset: u16,
-) {
+) -> Result<(), VmError> {
     let value_to_write = virtual_machine.read_register(source_register);
     let memory_address = virtual_machine
         .read_register(Register::ProgramCounter)
         .wrapping_add(program_counter_offset);
-    virtual_machine.memory_write(memory_address, value_to_write);
+    virtual_machine.checked_memory_write(memory_address, value_to_write)
 }
 
 pub fn store_indirect(
     virtual_machine: &mut LC3VirtualMachine,
     source_register: Register,
     program_counter_offset: u16,
-) {
+) -> Result<(), VmError> {
     let value_to_write = virtual_machine.read_register(source_register);
 
-    let memory_address = virtual_machine.memory_read(
+    let destination_address = virtual_machine.checked_memory_read(
         virtual_machine
             .read_register(Register::ProgramCounter)
             .wrapping_add(program_counter_offset),
-    );
+    )?;
 
-    let destination_address = virtual_machine.memory_read(memory_address);
-
-    virtual_machine.memory_write(destination_address, value_to_write);
+    virtual_machine.checked_memory_write(destination_address, value_to_write)
 }
 
 pub fn store_base_offset(
@@ -224,16 +252,43 @@ pub fn store_base_offset(
     source_register: Register,
     base_register: Register,
     offset: u16,
-) {
+) -> Result<(), VmError> {
     let value_to_write = virtual_machine.read_register(source_register);
     let base_register_address = virtual_machine.read_register(base_register);
 
-    virtual_machine.memory_write(base_register_address.wrapping_add(offset), value_to_write)
+    virtual_machine.checked_memory_write(base_register_address.wrapping_add(offset), value_to_write)
+}
+
+pub fn return_from_interrupt(virtual_machine: &mut LC3VirtualMachine) -> Result<(), VmError> {
+    if virtual_machine.privilege_mode() == PrivilegeMode::User {
+        return Err(VmError::PrivilegeModeViolation);
+    }
+    let program_counter = virtual_machine.pop_stack()?;
+    let processor_status = virtual_machine.pop_stack()?;
+    virtual_machine.update_register(Register::ProgramCounter, program_counter);
+    virtual_machine.update_processor_status(processor_status);
+
+    if virtual_machine.privilege_mode() == PrivilegeMode::User {
+        virtual_machine.update_register(
+            Register::SavedSupervisorStackPointer,
+            virtual_machine.read_register(Register::R6),
+        );
+        virtual_machine.update_register(
+            Register::R6,
+            virtual_machine.read_register(Register::SavedUserStackPointer),
+        );
+    }
+    Ok(())
 }
 
 #[cfg(test)]
+#[allow(clippy::unusual_byte_groupings)]
 pub mod test {
-    use crate::virtual_machine::register::Flag;
+    use crate::virtual_machine::{
+        error::VmError,
+        lc3_virtual_machine::StepOutcome,
+        register::{Flag, PrivilegeMode},
+    };
 
     use super::LC3VirtualMachine;
 
@@ -241,7 +296,9 @@ pub mod test {
     fn can_add_two_numbers_in_same_register() {
         let mut virtual_machine = LC3VirtualMachine::new(0);
         let add_one_to_register_zero = 0b0001_000_000_1_00001;
-        virtual_machine.decode_instruction(add_one_to_register_zero);
+        virtual_machine
+            .decode_instruction(add_one_to_register_zero)
+            .unwrap();
         let result = virtual_machine.read_register(super::Register::R0);
 
         assert_eq!(result, 1);
@@ -252,10 +309,16 @@ pub mod test {
         let mut virtual_machine = LC3VirtualMachine::new(0);
         let add_one_to_register_zero = 0b0001_000_000_1_00001;
         let add_one_to_register_one = 0b0001_001_001_1_00001;
-        virtual_machine.decode_instruction(add_one_to_register_zero);
-        virtual_machine.decode_instruction(add_one_to_register_one);
+        virtual_machine
+            .decode_instruction(add_one_to_register_zero)
+            .unwrap();
+        virtual_machine
+            .decode_instruction(add_one_to_register_one)
+            .unwrap();
         let add_register_zero_and_one_in_register_two = 0b0001_010_000_0_00001;
-        virtual_machine.decode_instruction(add_register_zero_and_one_in_register_two);
+        virtual_machine
+            .decode_instruction(add_register_zero_and_one_in_register_two)
+            .unwrap();
         let result = virtual_machine.read_register(super::Register::R2);
 
         assert_eq!(result, 2);
@@ -266,8 +329,12 @@ pub mod test {
         let mut virtual_machine = LC3VirtualMachine::new(0);
         let add_max_inmediate_value_to_register_zero = 0b0001_000_000_1_11111;
         let and_five_to_register_zero = 0b0101_000_000_1_00101;
-        virtual_machine.decode_instruction(add_max_inmediate_value_to_register_zero);
-        virtual_machine.decode_instruction(and_five_to_register_zero);
+        virtual_machine
+            .decode_instruction(add_max_inmediate_value_to_register_zero)
+            .unwrap();
+        virtual_machine
+            .decode_instruction(and_five_to_register_zero)
+            .unwrap();
         let result = virtual_machine.read_register(super::Register::R0);
 
         assert_eq!(result, 0b00101);
@@ -278,10 +345,16 @@ pub mod test {
         let mut virtual_machine = LC3VirtualMachine::new(0);
         let add_max_inmediate_value_to_register_zero = 0b0001_000_000_111111;
         let add_five_to_register_one = 0b0001_001_001_1_00101;
-        virtual_machine.decode_instruction(add_max_inmediate_value_to_register_zero);
-        virtual_machine.decode_instruction(add_five_to_register_one);
+        virtual_machine
+            .decode_instruction(add_max_inmediate_value_to_register_zero)
+            .unwrap();
+        virtual_machine
+            .decode_instruction(add_five_to_register_one)
+            .unwrap();
         let and_register_zero_and_one_in_register_two = 0b0101_010_000_0_00001;
-        virtual_machine.decode_instruction(and_register_zero_and_one_in_register_two);
+        virtual_machine
+            .decode_instruction(and_register_zero_and_one_in_register_two)
+            .unwrap();
         let result = virtual_machine.read_register(super::Register::R2);
 
         assert_eq!(result, 0b00101);
@@ -291,9 +364,13 @@ pub mod test {
     fn can_negate_the_values_of_two_registers() {
         let mut virtual_machine = LC3VirtualMachine::new(0);
         let add_five_to_register_zero = 0b0001_000_000_1_00101;
-        virtual_machine.decode_instruction(add_five_to_register_zero);
+        virtual_machine
+            .decode_instruction(add_five_to_register_zero)
+            .unwrap();
         let negate_register_zero = 0b1001_000_000_1_11111;
-        virtual_machine.decode_instruction(negate_register_zero);
+        virtual_machine
+            .decode_instruction(negate_register_zero)
+            .unwrap();
         let result = virtual_machine.read_register(super::Register::R0);
 
         assert_eq!(result, 0b1111111111111010);
@@ -303,9 +380,13 @@ pub mod test {
     fn can_branch_if_positive_flag() {
         let mut virtual_machine = LC3VirtualMachine::new(0);
         let add_one_to_register_zero = 0b0001_000_000_1_00001;
-        virtual_machine.decode_instruction(add_one_to_register_zero);
+        virtual_machine
+            .decode_instruction(add_one_to_register_zero)
+            .unwrap();
         let branch_positive_flag = 0b0000_0_0_1_000000010;
-        virtual_machine.decode_instruction(branch_positive_flag);
+        virtual_machine
+            .decode_instruction(branch_positive_flag)
+            .unwrap();
 
         let result = virtual_machine.read_register(super::Register::ConditionFlag);
         assert_eq!(result, Flag::POSITIVE as u16);
@@ -318,9 +399,13 @@ pub mod test {
     fn can_branch_if_negative_flag() {
         let mut virtual_machine = LC3VirtualMachine::new(0);
         let add_negative_number_to_register_zero = 0b0001_000_000_110001;
-        virtual_machine.decode_instruction(add_negative_number_to_register_zero);
+        virtual_machine
+            .decode_instruction(add_negative_number_to_register_zero)
+            .unwrap();
         let branch_negative_flag = 0b0000_1_0_0_000000010;
-        virtual_machine.decode_instruction(branch_negative_flag);
+        virtual_machine
+            .decode_instruction(branch_negative_flag)
+            .unwrap();
 
         let result = virtual_machine.read_register(super::Register::ConditionFlag);
         assert_eq!(result, Flag::NEGATIVE as u16);
@@ -333,9 +418,13 @@ pub mod test {
     fn can_branch_if_zero_flag() {
         let mut virtual_machine = LC3VirtualMachine::new(0);
         let add_zero_to_register_zero = 0b0001_000_000_100000;
-        virtual_machine.decode_instruction(add_zero_to_register_zero);
+        virtual_machine
+            .decode_instruction(add_zero_to_register_zero)
+            .unwrap();
         let branch_zero_flag = 0b0000_0_1_0_000000010;
-        virtual_machine.decode_instruction(branch_zero_flag);
+        virtual_machine
+            .decode_instruction(branch_zero_flag)
+            .unwrap();
 
         let result = virtual_machine.read_register(super::Register::ConditionFlag);
         assert_eq!(result, Flag::ZERO as u16);
@@ -348,11 +437,17 @@ pub mod test {
     fn can_store_and_load_from_memory() {
         let mut virtual_machine = LC3VirtualMachine::new(0);
         let add_five_to_register_zero = 0b0001_000_000_1_00101;
-        virtual_machine.decode_instruction(add_five_to_register_zero);
+        virtual_machine
+            .decode_instruction(add_five_to_register_zero)
+            .unwrap();
         let store_register_zero_value_to_memory = 0b0011_000_000000001;
-        virtual_machine.decode_instruction(store_register_zero_value_to_memory);
+        virtual_machine
+            .decode_instruction(store_register_zero_value_to_memory)
+            .unwrap();
         let load_value_from_memory_to_register_one = 0b0010_001_000000001;
-        virtual_machine.decode_instruction(load_value_from_memory_to_register_one);
+        virtual_machine
+            .decode_instruction(load_value_from_memory_to_register_one)
+            .unwrap();
 
         let result = virtual_machine.read_register(super::Register::R1);
         assert_eq!(result, 0b101);
@@ -362,46 +457,137 @@ pub mod test {
     fn can_jump_to_subroutine_and_return_with_register_seven() {
         let mut virtual_machine = LC3VirtualMachine::new(0);
         let jump_to_position_four = 0b0100_1_00000000100;
-        virtual_machine.decode_instruction(jump_to_position_four);
+        virtual_machine
+            .decode_instruction(jump_to_position_four)
+            .unwrap();
 
         let result = virtual_machine.read_register(super::Register::ProgramCounter);
         assert_eq!(result, 0b100);
         let jump_to_register_zero = 0b0100_0_00_000_000000;
-        virtual_machine.decode_instruction(jump_to_register_zero);
+        virtual_machine
+            .decode_instruction(jump_to_register_zero)
+            .unwrap();
         let result = virtual_machine.read_register(super::Register::ProgramCounter);
         assert_eq!(result, 0);
     }
 
+    #[test]
+    fn jump_to_subroutine_in_register_seven_uses_its_old_value() {
+        let mut virtual_machine = LC3VirtualMachine::new(0x3000);
+        virtual_machine.update_register(super::Register::R7, 0x4000);
+        let jump_to_register_seven = 0b0100_0_00_111_000000;
+        virtual_machine
+            .decode_instruction(jump_to_register_seven)
+            .unwrap();
+
+        assert_eq!(
+            virtual_machine.read_register(super::Register::ProgramCounter),
+            0x4000
+        );
+        assert_eq!(virtual_machine.read_register(super::Register::R7), 0x3000);
+    }
+
+    #[test]
+    fn subroutine_calls_and_returns_keep_the_shadow_call_stack() {
+        let mut virtual_machine = LC3VirtualMachine::new(0x3000);
+        let jump_to_subroutine_two_ahead = 0b0100_1_00000000010;
+        let return_from_subroutine = 0b1100_000_111_000000;
+        let save_return_address_in_register_one = 0b0001_001_111_1_00000;
+        let jump_to_subroutine_in_register_two = 0b0100_0_00_010_000000;
+        let restore_return_address_from_register_one = 0b0001_111_001_1_00000;
+        virtual_machine.update_register(super::Register::R2, 0x3010);
+        virtual_machine
+            .memory_write(0x3000, jump_to_subroutine_two_ahead)
+            .unwrap();
+        virtual_machine
+            .memory_write(0x3003, save_return_address_in_register_one)
+            .unwrap();
+        virtual_machine
+            .memory_write(0x3004, jump_to_subroutine_in_register_two)
+            .unwrap();
+        virtual_machine
+            .memory_write(0x3005, restore_return_address_from_register_one)
+            .unwrap();
+        virtual_machine
+            .memory_write(0x3006, return_from_subroutine)
+            .unwrap();
+        virtual_machine
+            .memory_write(0x3010, return_from_subroutine)
+            .unwrap();
+        virtual_machine.next_instructions(3).unwrap();
+
+        let call_addresses: Vec<(u16, u16)> = virtual_machine
+            .call_stack()
+            .iter()
+            .map(|call_frame| (call_frame.call_address, call_frame.subroutine_address))
+            .collect();
+        assert_eq!(call_addresses, vec![(0x3000, 0x3003), (0x3004, 0x3010)]);
+        virtual_machine.next_instruction().unwrap();
+        assert_eq!(virtual_machine.call_stack().len(), 1);
+        virtual_machine.next_instructions(2).unwrap();
+        assert!(virtual_machine.call_stack().is_empty());
+        assert_eq!(
+            virtual_machine.read_register(super::Register::ProgramCounter),
+            0x3001
+        );
+    }
+
     #[test]
     fn can_store_and_load_from_memory_with_base_and_offset() {
         let mut virtual_machine = LC3VirtualMachine::new(0);
         let add_five_to_register_zero = 0b0001_000_000_1_00101;
-        virtual_machine.decode_instruction(add_five_to_register_zero);
+        virtual_machine
+            .decode_instruction(add_five_to_register_zero)
+            .unwrap();
         let add_five_to_register_one = 0b0001_001_001_1_00101;
-        virtual_machine.decode_instruction(add_five_to_register_one);
+        virtual_machine
+            .decode_instruction(add_five_to_register_one)
+            .unwrap();
 
         let store_register_zero_value_to_memory_from_register_one_and_one_offset =
             0b0111_000_001_000001;
-        virtual_machine.decode_instruction(
-            store_register_zero_value_to_memory_from_register_one_and_one_offset,
-        );
+        virtual_machine
+            .decode_instruction(
+                store_register_zero_value_to_memory_from_register_one_and_one_offset,
+            )
+            .unwrap();
         let load_value_from_memory_from_register_one_and_one_offset_to_register_two =
             0b0110_010_001_000001;
-        virtual_machine.decode_instruction(
-            load_value_from_memory_from_register_one_and_one_offset_to_register_two,
-        );
+        virtual_machine
+            .decode_instruction(
+                load_value_from_memory_from_register_one_and_one_offset_to_register_two,
+            )
+            .unwrap();
 
         let result = virtual_machine.read_register(super::Register::R2);
         assert_eq!(result, 0b101);
     }
 
+    #[test]
+    fn store_indirect_writes_where_the_pointer_points() {
+        let mut virtual_machine = LC3VirtualMachine::new(0x3000);
+        virtual_machine.update_register(super::Register::R0, 5);
+        virtual_machine.memory_write(0x3001, 0x4000).unwrap();
+        let store_register_zero_through_pointer = 0b1011_000_000000001;
+        virtual_machine
+            .decode_instruction(store_register_zero_through_pointer)
+            .unwrap();
+
+        assert_eq!(virtual_machine.memory_read(0x4000).unwrap(), 5);
+        assert_eq!(virtual_machine.memory_read(0x0000).unwrap(), 0);
+    }
+
     #[test]
     fn can_unconditionally_jumps() {
         let mut virtual_machine = LC3VirtualMachine::new(0);
         let add_five_to_register_zero = 0b0001_000_000_1_00101;
-        virtual_machine.decode_instruction(add_five_to_register_zero);
+        virtual_machine
+            .decode_instruction(add_five_to_register_zero)
+            .unwrap();
         let unconditionally_jump_to_register_zero_value = 0b1100_000_000_000000;
-        virtual_machine.decode_instruction(unconditionally_jump_to_register_zero_value);
+        virtual_machine
+            .decode_instruction(unconditionally_jump_to_register_zero_value)
+            .unwrap();
 
         let result = virtual_machine.read_register(super::Register::ProgramCounter);
         assert_eq!(result, 0b101);
@@ -411,9 +597,111 @@ pub mod test {
     fn can_load_effective_address() {
         let mut virtual_machine = LC3VirtualMachine::new(0);
         let load_effective_address_three_to_register_zero = 0b1110_000_000000011;
-        virtual_machine.decode_instruction(load_effective_address_three_to_register_zero);
+        virtual_machine
+            .decode_instruction(load_effective_address_three_to_register_zero)
+            .unwrap();
 
         let result = virtual_machine.read_register(super::Register::R0);
         assert_eq!(result, 0b11);
     }
+
+    #[test]
+    fn reserved_opcode_returns_illegal_opcode_error() {
+        let mut virtual_machine = LC3VirtualMachine::new(0);
+        let reserved_instruction = 0b1101_000_000_000000;
+        let result = virtual_machine.decode_instruction(reserved_instruction);
+
+        assert!(matches!(
+            result,
+            Err(VmError::IllegalOpcode(0b1101_000_000_000000))
+        ));
+    }
+
+    #[test]
+    fn unknown_trap_vector_returns_unknown_trap_error() {
+        let mut virtual_machine = LC3VirtualMachine::new(0);
+        let trap_with_unknown_vector = 0b1111_0000_11111111;
+        let result = virtual_machine.decode_instruction(trap_with_unknown_vector);
+
+        assert!(matches!(result, Err(VmError::UnknownTrap(0xFF))));
+    }
+
+    #[test]
+    fn halt_trap_returns_halted_outcome() {
+        let mut virtual_machine = LC3VirtualMachine::new(0);
+        let halt = 0b1111_0000_00100101;
+        let result = virtual_machine.decode_instruction(halt).unwrap();
+
+        assert_eq!(result, StepOutcome::Halted);
+    }
+
+    #[test]
+    fn return_from_interrupt_restores_program_counter_and_user_stack() {
+        let mut virtual_machine = LC3VirtualMachine::new(0x0200);
+        virtual_machine.update_register(super::Register::R6, 0x2FFE);
+        virtual_machine.update_register(super::Register::SavedUserStackPointer, 0xFDFF);
+        virtual_machine.memory_write(0x2FFE, 0x3005).unwrap();
+        virtual_machine.memory_write(0x2FFF, 0x8001).unwrap();
+        let return_from_interrupt = 0b1000_0000_0000_0000;
+        virtual_machine
+            .decode_instruction(return_from_interrupt)
+            .unwrap();
+
+        assert_eq!(
+            virtual_machine.read_register(super::Register::ProgramCounter),
+            0x3005
+        );
+        assert_eq!(virtual_machine.processor_status(), 0x8001);
+        assert_eq!(virtual_machine.privilege_mode(), PrivilegeMode::User);
+        assert_eq!(virtual_machine.read_register(super::Register::R6), 0xFDFF);
+        assert_eq!(
+            virtual_machine.read_register(super::Register::SavedSupervisorStackPointer),
+            0x3000
+        );
+    }
+
+    #[test]
+    fn return_from_interrupt_in_user_mode_is_a_privilege_violation() {
+        let mut virtual_machine = LC3VirtualMachine::new(0x3000);
+        virtual_machine.update_processor_status(0x8002);
+        let return_from_interrupt = 0b1000_0000_0000_0000;
+        let result = virtual_machine.decode_instruction(return_from_interrupt);
+
+        assert!(matches!(result, Err(VmError::PrivilegeModeViolation)));
+    }
+
+    #[test]
+    fn trap_in_operating_system_mode_runs_the_service_routine_from_the_vector_table() {
+        let mut virtual_machine = LC3VirtualMachine::new(0x3000);
+        virtual_machine.enable_operating_system_mode();
+        virtual_machine.enter_user_mode(0x3000);
+        let trap_x40 = 0xF040;
+        virtual_machine.memory_write(0x3000, trap_x40).unwrap();
+        virtual_machine.memory_write(0x0040, 0x0400).unwrap();
+        let add_one_to_register_zero = 0b0001_000_000_1_00001;
+        virtual_machine
+            .memory_write(0x0400, add_one_to_register_zero)
+            .unwrap();
+        let return_from_interrupt = 0b1000_0000_0000_0000;
+        virtual_machine
+            .memory_write(0x0401, return_from_interrupt)
+            .unwrap();
+
+        virtual_machine.next_instruction().unwrap();
+        assert_eq!(
+            virtual_machine.read_register(super::Register::ProgramCounter),
+            0x0400
+        );
+        assert_eq!(virtual_machine.privilege_mode(), PrivilegeMode::Supervisor);
+        assert_eq!(virtual_machine.read_register(super::Register::R7), 0x3001);
+
+        virtual_machine.next_instructions(2).unwrap();
+        assert_eq!(virtual_machine.read_register(super::Register::R0), 1);
+        assert_eq!(
+            virtual_machine.read_register(super::Register::ProgramCounter),
+            0x3001
+        );
+        assert_eq!(virtual_machine.privilege_mode(), PrivilegeMode::User);
+        assert_eq!(virtual_machine.read_register(super::Register::R6), 0xFE00);
+    }
 }
@@ -0,0 +1,123 @@
+use std::{io::Error, ops::RangeInclusive};
+
+use super::{
+    console::Console,
+    error::VmError,
+    lc3_virtual_machine::Interrupt,
+    memory_bus::{Device, MemoryMappedRegister},
+};
+
+pub const KEYBOARD_READY_BIT: u16 = 1 << 15;
+const KEYBOARD_INTERRUPT_ENABLE_BIT: u16 = 1 << 14;
+const KEYBOARD_INTERRUPT_VECTOR: u16 = 0x80;
+const KEYBOARD_PRIORITY_LEVEL: u16 = 4;
+
+#[derive(Default)]
+pub struct Keyboard {
+    ready: bool,
+    interrupt_enabled: bool,
+    data: u16,
+}
+
+impl Keyboard {
+    fn poll(&mut self, console: &mut dyn Console) -> Result<(), Error> {
+        if !self.ready {
+            if let Some(char) = console.poll_byte()? {
+                self.ready = true;
+                self.data = char as u16;
+            }
+        }
+        Ok(())
+    }
+
+    fn status(&self) -> u16 {
+        let mut status = 0;
+        if self.ready {
+            status |= KEYBOARD_READY_BIT;
+        }
+        if self.interrupt_enabled {
+            status |= KEYBOARD_INTERRUPT_ENABLE_BIT;
+        }
+        status
+    }
+}
+
+impl Device for Keyboard {
+    fn address_range(&self) -> RangeInclusive<u16> {
+        MemoryMappedRegister::KeyBoardStatus as u16..=MemoryMappedRegister::KeyBoardData as u16
+    }
+
+    fn read(&mut self, memory_address: u16, console: &mut dyn Console) -> Result<u16, VmError> {
+        if memory_address == MemoryMappedRegister::KeyBoardStatus as u16 {
+            self.poll(console)?;
+            return Ok(self.status());
+        }
+        if memory_address == MemoryMappedRegister::KeyBoardData as u16 {
+            self.ready = false;
+            return Ok(self.data);
+        }
+        Ok(0)
+    }
+
+    fn write(
+        &mut self,
+        memory_address: u16,
+        value_to_write: u16,
+        _console: &mut dyn Console,
+    ) -> Result<(), VmError> {
+        if memory_address == MemoryMappedRegister::KeyBoardStatus as u16 {
+            self.interrupt_enabled = value_to_write & KEYBOARD_INTERRUPT_ENABLE_BIT != 0;
+        }
+        Ok(())
+    }
+
+    fn pending_interrupt(
+        &mut self,
+        console: &mut dyn Console,
+    ) -> Result<Option<Interrupt>, VmError> {
+        if !self.interrupt_enabled {
+            return Ok(None);
+        }
+        self.poll(console)?;
+        if !self.ready {
+            return Ok(None);
+        }
+        Ok(Some(Interrupt {
+            vector: KEYBOARD_INTERRUPT_VECTOR,
+            priority_level: KEYBOARD_PRIORITY_LEVEL,
+        }))
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use crate::virtual_machine::{console::BufferConsole, lc3_virtual_machine::LC3VirtualMachine};
+
+    #[test]
+    fn keyboard_status_is_not_ready_without_input() {
+        let console = BufferConsole::default();
+        let mut virtual_machine = LC3VirtualMachine::with_console(0x3000, console);
+        let keyboard_status = virtual_machine.memory_read(0xFE00).unwrap();
+
+        assert_eq!(keyboard_status & 0x8000, 0);
+    }
+
+    #[test]
+    fn reading_keyboard_data_consumes_the_waiting_char() {
+        let console = BufferConsole::default();
+        let mut virtual_machine = LC3VirtualMachine::with_console(0x3000, console.clone());
+        console.push_input(b"ab");
+
+        assert_eq!(
+            virtual_machine.memory_read(0xFE00).unwrap() & 0x8000,
+            0x8000
+        );
+        assert_eq!(virtual_machine.memory_read(0xFE02).unwrap(), b'a' as u16);
+        assert_eq!(
+            virtual_machine.memory_read(0xFE00).unwrap() & 0x8000,
+            0x8000
+        );
+        assert_eq!(virtual_machine.memory_read(0xFE02).unwrap(), b'b' as u16);
+        assert_eq!(virtual_machine.memory_read(0xFE00).unwrap() & 0x8000, 0);
+    }
+}
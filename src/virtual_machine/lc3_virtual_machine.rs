@@ -1,26 +1,204 @@
-use std::io::Read;
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use crate::constants::DEFAULT_USER_STACK_POINTER;
 
 use super::{
+    console::{Console, StandardConsole},
+    display::Display,
+    error::VmError,
     instructions::*,
-    register::{Register, Registers},
-    trap::Trap,
+    keyboard::{Keyboard, KEYBOARD_READY_BIT},
+    machine_control::MachineControl,
+    memory_bus::{Device, MemoryAccess, MemoryBus, MemoryHook, MemoryMappedRegister},
+    register::{PrivilegeMode, Register, Registers},
+    trap::{Trap, TrapHandler},
 };
 
-enum MemoryMappedRegister {
-    KeyBoardStatusRegister = 0xFE00,
-    KeyBoardDataRegister = 0xFE02,
+const INSTRUCTIONS_BETWEEN_CLOCK_CHECKS: u64 = 1024;
+/// Programs that leave subroutines without `RET` only keep their latest calls.
+const MAXIMUM_CALL_DEPTH: usize = 1024;
+
+pub const INTERRUPT_VECTOR_TABLE_START: u16 = 0x0100;
+pub const USER_SPACE_START: u16 = 0x3000;
+pub const IO_PAGE_START: u16 = 0xFE00;
+
+pub struct Interrupt {
+    pub vector: u16,
+    pub priority_level: u16,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StepOutcome {
+    Continue,
+    Halted,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RunLimits {
+    pub max_instructions: Option<u64>,
+    pub timeout: Option<Duration>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RunOutcome {
+    Halted,
+    InstructionLimitReached,
+    TimedOut,
+}
+
+/// A `JSR` or `JSRR` that hasn't returned yet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CallFrame {
+    pub call_address: u16,
+    pub subroutine_address: u16,
+}
+
+impl CallFrame {
+    pub fn return_address(&self) -> u16 {
+        self.call_address.wrapping_add(1)
+    }
 }
 
 pub struct LC3VirtualMachine {
     registers: Registers,
-    memory: Vec<u16>,
+    memory_bus: MemoryBus,
+    machine_control: MachineControl,
+    console: Box<dyn Console>,
+    operating_system_mode: bool,
+    trap_handlers: HashMap<u8, TrapHandler>,
+    memory_hook: Option<MemoryHook>,
+    call_stack: Vec<CallFrame>,
+    input_deadline: Option<Instant>,
 }
 
 impl LC3VirtualMachine {
     pub fn new(program_counter_start: u16) -> Self {
+        Self::with_console(program_counter_start, StandardConsole)
+    }
+
+    pub fn with_console(program_counter_start: u16, console: impl Console + 'static) -> Self {
+        let mut memory_bus = MemoryBus::new();
+        memory_bus.attach_device(Keyboard::default());
+        memory_bus.attach_device(Display::default());
+        let machine_control = MachineControl::default();
+        memory_bus.attach_device(machine_control.clone());
         Self {
             registers: Registers::new(program_counter_start),
-            memory: vec![0; 1 << 16],
+            memory_bus,
+            machine_control,
+            console: Box::new(console),
+            operating_system_mode: false,
+            trap_handlers: HashMap::new(),
+            memory_hook: None,
+            call_stack: Vec::new(),
+            input_deadline: None,
+        }
+    }
+
+    pub fn enable_operating_system_mode(&mut self) {
+        self.operating_system_mode = true;
+    }
+
+    pub fn is_operating_system_mode(&self) -> bool {
+        self.operating_system_mode
+    }
+
+    pub fn enter_user_mode(&mut self, program_counter_start: u16) {
+        self.registers.update_privilege_mode(PrivilegeMode::User);
+        self.update_register(Register::ProgramCounter, program_counter_start);
+        self.update_register(Register::R6, DEFAULT_USER_STACK_POINTER);
+    }
+
+    pub fn attach_device(&mut self, device: impl Device + 'static) {
+        self.memory_bus.attach_device(device);
+    }
+
+    /// Registered handlers take precedence over the built-in traps and the OS trap vector table.
+    pub fn register_trap(
+        &mut self,
+        trap_vector: u8,
+        handler: impl FnMut(&mut LC3VirtualMachine) -> Result<StepOutcome, VmError> + 'static,
+    ) {
+        self.trap_handlers.insert(trap_vector, Box::new(handler));
+    }
+
+    pub fn unregister_trap(&mut self, trap_vector: u8) -> bool {
+        self.trap_handlers.remove(&trap_vector).is_some()
+    }
+
+    fn execute_trap(&mut self, trap_vector: u16) -> Result<StepOutcome, VmError> {
+        if let Some(mut handler) = self.trap_handlers.remove(&(trap_vector as u8)) {
+            self.update_register(Register::R7, self.read_register(Register::ProgramCounter));
+            let step_outcome = handler(self);
+            self.trap_handlers
+                .entry(trap_vector as u8)
+                .or_insert(handler);
+            return step_outcome;
+        }
+        if self.operating_system_mode {
+            trap_through_vector_table(self, trap_vector)?;
+            return Ok(StepOutcome::Continue);
+        }
+        let trap = Trap::try_from(trap_vector)?;
+        trap_instruction(self, trap)
+    }
+
+    /// Called after every data read and write, but not on instruction fetches.
+    pub fn set_memory_hook(&mut self, hook: impl FnMut(MemoryAccess) + 'static) {
+        self.memory_hook = Some(Box::new(hook));
+    }
+
+    pub fn clear_memory_hook(&mut self) {
+        self.memory_hook = None;
+    }
+
+    /// The shadow call stack kept by `JSR`, `JSRR` and `RET`, innermost call last.
+    pub fn call_stack(&self) -> &[CallFrame] {
+        &self.call_stack
+    }
+
+    pub fn enter_subroutine(&mut self, call_address: u16, subroutine_address: u16) {
+        if self.call_stack.len() == MAXIMUM_CALL_DEPTH {
+            self.call_stack.remove(0);
+        }
+        self.call_stack.push(CallFrame {
+            call_address,
+            subroutine_address,
+        });
+    }
+
+    /// Unwinds to the call returning to `return_address`. A `RET` that doesn't match any call
+    /// is a computed jump and leaves the stack as it is.
+    pub fn return_from_subroutine(&mut self, return_address: u16) {
+        if let Some(index) = self
+            .call_stack
+            .iter()
+            .rposition(|call_frame| call_frame.return_address() == return_address)
+        {
+            self.call_stack.truncate(index);
+        }
+    }
+
+    pub fn console(&mut self) -> &mut dyn Console {
+        self.console.as_mut()
+    }
+
+    pub fn is_halted(&self) -> bool {
+        !self.machine_control.is_clock_enabled()
+    }
+
+    pub fn halt(&mut self) {
+        self.machine_control.stop_clock();
+    }
+
+    fn step_outcome(&self) -> StepOutcome {
+        if self.is_halted() {
+            StepOutcome::Halted
+        } else {
+            StepOutcome::Continue
         }
     }
 
@@ -37,44 +215,178 @@ impl LC3VirtualMachine {
         self.registers.update_flags(register)
     }
 
-    fn receive_keyboard_input(&mut self) {
-        let mut buffer = [0; 1];
-        std::io::stdin()
-            .read_exact(&mut buffer)
-            .expect("Couldn't read from stdin");
-        if buffer[0] != 0 {
-            self.memory_write(MemoryMappedRegister::KeyBoardStatusRegister as u16, 1 << 15);
-            self.memory_write(
-                MemoryMappedRegister::KeyBoardDataRegister as u16,
-                buffer[0] as u16,
+    pub fn processor_status(&self) -> u16 {
+        self.registers.processor_status()
+    }
+
+    pub fn update_processor_status(&mut self, processor_status: u16) {
+        self.registers.update_processor_status(processor_status)
+    }
+
+    pub fn privilege_mode(&self) -> PrivilegeMode {
+        self.registers.privilege_mode()
+    }
+
+    pub fn push_stack(&mut self, value: u16) -> Result<(), VmError> {
+        let stack_pointer = self.read_register(Register::R6).wrapping_sub(1);
+        self.update_register(Register::R6, stack_pointer);
+        self.memory_write(stack_pointer, value)
+    }
+
+    pub fn pop_stack(&mut self) -> Result<u16, VmError> {
+        let stack_pointer = self.read_register(Register::R6);
+        let value = self.memory_read(stack_pointer)?;
+        self.update_register(Register::R6, stack_pointer.wrapping_add(1));
+        Ok(value)
+    }
+
+    pub fn read_input_byte(&mut self) -> Result<u8, VmError> {
+        let keyboard_status = self.memory_read(MemoryMappedRegister::KeyBoardStatus as u16)?;
+        if keyboard_status & KEYBOARD_READY_BIT != 0 {
+            let char = self.memory_read(MemoryMappedRegister::KeyBoardData as u16)?;
+            return Ok(char as u8);
+        }
+        match self.input_deadline {
+            Some(deadline) => self
+                .console
+                .read_byte_until(deadline)?
+                .ok_or(VmError::InputTimedOut),
+            None => Ok(self.console.read_byte()?),
+        }
+    }
+
+    pub fn memory_read(&mut self, memory_address: u16) -> Result<u16, VmError> {
+        let value = self.unobserved_memory_read(memory_address)?;
+        if let Some(hook) = self.memory_hook.as_mut() {
+            hook(MemoryAccess::Read {
+                address: memory_address,
+                value,
+            });
+        }
+        Ok(value)
+    }
+
+    /// Reads a word without side effects or the memory hook. None for device registers.
+    pub fn peek_memory(&self, memory_address: u16) -> Option<u16> {
+        self.memory_bus.peek(memory_address)
+    }
+
+    fn unobserved_memory_read(&mut self, memory_address: u16) -> Result<u16, VmError> {
+        self.memory_bus.read(memory_address, self.console.as_mut())
+    }
+
+    pub fn memory_write(
+        &mut self,
+        memory_address: u16,
+        value_to_write: u16,
+    ) -> Result<(), VmError> {
+        let old_value = self.memory_bus.peek(memory_address);
+        self.memory_bus
+            .write(memory_address, value_to_write, self.console.as_mut())?;
+        if let Some(hook) = self.memory_hook.as_mut() {
+            hook(MemoryAccess::Write {
+                address: memory_address,
+                old_value,
+                new_value: value_to_write,
+            });
+        }
+        Ok(())
+    }
+
+    pub fn check_access_control(&self, memory_address: u16) -> Result<(), VmError> {
+        let is_system_address = !(USER_SPACE_START..IO_PAGE_START).contains(&memory_address);
+        if is_system_address && self.privilege_mode() == PrivilegeMode::User {
+            return Err(VmError::AccessControlViolation(memory_address));
+        }
+        Ok(())
+    }
+
+    pub fn checked_memory_read(&mut self, memory_address: u16) -> Result<u16, VmError> {
+        self.check_access_control(memory_address)?;
+        self.memory_read(memory_address)
+    }
+
+    pub fn checked_memory_write(
+        &mut self,
+        memory_address: u16,
+        value_to_write: u16,
+    ) -> Result<(), VmError> {
+        self.check_access_control(memory_address)?;
+        self.memory_write(memory_address, value_to_write)
+    }
+
+    fn pending_interrupt(&mut self) -> Result<Option<Interrupt>, VmError> {
+        let interrupt = self.memory_bus.pending_interrupt(self.console.as_mut())?;
+        Ok(
+            interrupt
+                .filter(|interrupt| interrupt.priority_level > self.registers.priority_level()),
+        )
+    }
+
+    fn switch_to_supervisor_stack(&mut self) {
+        if self.privilege_mode() == PrivilegeMode::User {
+            self.update_register(
+                Register::SavedUserStackPointer,
+                self.read_register(Register::R6),
             );
-        } else {
-            self.memory_write(MemoryMappedRegister::KeyBoardStatusRegister as u16, 0)
+            self.update_register(
+                Register::R6,
+                self.read_register(Register::SavedSupervisorStackPointer),
+            );
+            self.registers
+                .update_privilege_mode(PrivilegeMode::Supervisor);
         }
     }
 
-    pub fn memory_read(&mut self, memory_address: u16) -> u16 {
-        if memory_address == MemoryMappedRegister::KeyBoardStatusRegister as u16 {
-            self.receive_keyboard_input();
+    pub fn enter_service_routine(&mut self, handler_address: u16) -> Result<(), VmError> {
+        let processor_status = self.processor_status();
+        self.switch_to_supervisor_stack();
+        self.push_stack(processor_status)?;
+        self.push_stack(self.read_register(Register::ProgramCounter))?;
+        self.update_register(Register::ProgramCounter, handler_address);
+        Ok(())
+    }
+
+    pub fn initiate_interrupt(&mut self, interrupt: Interrupt) -> Result<(), VmError> {
+        let handler_address =
+            self.memory_read(INTERRUPT_VECTOR_TABLE_START.wrapping_add(interrupt.vector))?;
+        self.enter_service_routine(handler_address)?;
+        self.registers
+            .update_priority_level(interrupt.priority_level);
+        Ok(())
+    }
+
+    fn initiate_exception(&mut self, error: VmError) -> Result<StepOutcome, VmError> {
+        let Some(vector) = error.exception_vector() else {
+            return Err(error);
+        };
+        let handler_address =
+            self.memory_read(INTERRUPT_VECTOR_TABLE_START.wrapping_add(vector))?;
+        if handler_address == 0 {
+            return Err(error);
         }
-        self.memory[memory_address as usize]
+        self.enter_service_routine(handler_address)?;
+        Ok(StepOutcome::Continue)
     }
 
-    pub fn memory_write(&mut self, memory_address: u16, value_to_write: u16) {
-        self.memory[memory_address as usize] = value_to_write;
+    pub fn decode_instruction(&mut self, instruction: u16) -> Result<StepOutcome, VmError> {
+        match self.execute_instruction(instruction) {
+            Err(error) => self.initiate_exception(error),
+            step_outcome => step_outcome,
+        }
     }
 
-    pub fn decode_instruction(&mut self, instruction: u16) {
+    fn execute_instruction(&mut self, instruction: u16) -> Result<StepOutcome, VmError> {
         let instruction_opcode = instruction >> 12;
         match instruction_opcode {
             BR => {
                 let program_counter_offset = Self::sign_extend(instruction & 0b111111111, 9);
                 let conditions_flag = (instruction >> 9) & 0b111;
-                branch(self, program_counter_offset, conditions_flag)
+                branch(self, program_counter_offset, conditions_flag);
             }
             ADD => {
-                let destination_register = Register::from((instruction >> 9) & 0b111);
-                let source_one_register = Register::from((instruction >> 6) & 0b111);
+                let destination_register = Register::try_from((instruction >> 9) & 0b111)?;
+                let source_one_register = Register::try_from((instruction >> 6) & 0b111)?;
                 let inmediate_return_flag = (instruction >> 5) & 0b1;
                 if inmediate_return_flag == 1 {
                     let inmediate_value = Self::sign_extend(instruction & 0b11111, 5);
@@ -83,40 +395,40 @@ impl LC3VirtualMachine {
                         destination_register,
                         source_one_register,
                         inmediate_value,
-                    )
+                    );
                 } else {
-                    let source_two_register = Register::from(instruction & 0b111);
+                    let source_two_register = Register::try_from(instruction & 0b111)?;
                     add(
                         self,
                         destination_register,
                         source_one_register,
                         source_two_register,
-                    )
+                    );
                 }
             }
             LD => {
-                let destination_register = Register::from((instruction >> 9) & 0b111);
+                let destination_register = Register::try_from((instruction >> 9) & 0b111)?;
                 let program_counter_offset = Self::sign_extend(instruction & 0b111111111, 9);
-                load(self, destination_register, program_counter_offset)
+                load(self, destination_register, program_counter_offset)?;
             }
             ST => {
-                let source_register = Register::from((instruction >> 9) & 0b111);
+                let source_register = Register::try_from((instruction >> 9) & 0b111)?;
                 let program_counter_offset = Self::sign_extend(instruction & 0b111111111, 9);
-                store(self, source_register, program_counter_offset)
+                store(self, source_register, program_counter_offset)?;
             }
             JSR => {
                 let offset_flag = (instruction >> 11) & 0b1;
                 if offset_flag == 1 {
                     let program_counter_offset = Self::sign_extend(instruction & 0b11111111111, 11);
-                    jump_to_subroutine_with_offset(self, program_counter_offset)
+                    jump_to_subroutine_with_offset(self, program_counter_offset);
                 } else {
-                    let base_register = Register::from((instruction >> 6) & 0b111);
-                    jump_to_subroutine(self, base_register)
+                    let base_register = Register::try_from((instruction >> 6) & 0b111)?;
+                    jump_to_subroutine(self, base_register);
                 }
             }
             AND => {
-                let destination_register = Register::from((instruction >> 9) & 0b111);
-                let source_one_register = Register::from((instruction >> 6) & 0b111);
+                let destination_register = Register::try_from((instruction >> 9) & 0b111)?;
+                let source_one_register = Register::try_from((instruction >> 6) & 0b111)?;
                 let inmediate_return_flag = (instruction >> 5) & 0b1;
                 if inmediate_return_flag == 1 {
                     let inmediate_value = Self::sign_extend(instruction & 0b11111, 5);
@@ -125,75 +437,146 @@ impl LC3VirtualMachine {
                         destination_register,
                         source_one_register,
                         inmediate_value,
-                    )
+                    );
                 } else {
-                    let source_two_register = Register::from(instruction & 0b111);
+                    let source_two_register = Register::try_from(instruction & 0b111)?;
                     and(
                         self,
                         destination_register,
                         source_one_register,
                         source_two_register,
-                    )
+                    );
                 }
             }
             LDR => {
-                let destination_register = Register::from((instruction >> 9) & 0b111);
-                let base_register = Register::from((instruction >> 6) & 0b111);
+                let destination_register = Register::try_from((instruction >> 9) & 0b111)?;
+                let base_register = Register::try_from((instruction >> 6) & 0b111)?;
                 let offset = Self::sign_extend(instruction & 0b111111, 6);
-                load_base_offset(self, destination_register, base_register, offset)
+                load_base_offset(self, destination_register, base_register, offset)?;
             }
             STR => {
-                let source_register = Register::from((instruction >> 9) & 0b111);
-                let base_register = Register::from((instruction >> 6) & 0b111);
+                let source_register = Register::try_from((instruction >> 9) & 0b111)?;
+                let base_register = Register::try_from((instruction >> 6) & 0b111)?;
                 let offset = Self::sign_extend(instruction & 0b111111, 6);
-                store_base_offset(self, source_register, base_register, offset)
+                store_base_offset(self, source_register, base_register, offset)?;
             }
             NOT => {
-                let destination_register = Register::from((instruction >> 9) & 0b111);
-                let source_register = Register::from((instruction >> 6) & 0b111);
-                not(self, destination_register, source_register)
+                let destination_register = Register::try_from((instruction >> 9) & 0b111)?;
+                let source_register = Register::try_from((instruction >> 6) & 0b111)?;
+                not(self, destination_register, source_register);
             }
             LDI => {
-                let destination_register = Register::from((instruction >> 9) & 0b111);
+                let destination_register = Register::try_from((instruction >> 9) & 0b111)?;
                 let program_counter_offset = Self::sign_extend(instruction & 0b111111111, 9);
-                load_indirect(self, destination_register, program_counter_offset)
+                load_indirect(self, destination_register, program_counter_offset)?;
             }
             STI => {
-                let source_register = Register::from((instruction >> 9) & 0b111);
+                let source_register = Register::try_from((instruction >> 9) & 0b111)?;
                 let program_counter_offset = Self::sign_extend(instruction & 0b111111111, 9);
-                store_indirect(self, source_register, program_counter_offset)
+                store_indirect(self, source_register, program_counter_offset)?;
             }
             JMP => {
-                let base_register = Register::from((instruction >> 6) & 0b111);
-                jump(self, base_register)
+                let base_register = Register::try_from((instruction >> 6) & 0b111)?;
+                jump(self, base_register);
             }
             LEA => {
-                let destination_register = Register::from((instruction >> 9) & 0b111);
+                let destination_register = Register::try_from((instruction >> 9) & 0b111)?;
                 let program_counter_offset = Self::sign_extend(instruction & 0b111111111, 9);
-                load_effective_address(self, destination_register, program_counter_offset)
+                load_effective_address(self, destination_register, program_counter_offset);
             }
             TRAP => {
-                let trap = Trap::from(instruction & 0b11111111);
-                trap_instruction(self, trap)
+                let trap_vector = instruction & 0b11111111;
+                if self.execute_trap(trap_vector)? == StepOutcome::Halted {
+                    self.halt();
+                }
             }
-            RTI => panic!("This opcode is not supported"),
-            RES => panic!("This opcode is not supported"),
+            RTI => return_from_interrupt(self)?,
+            RES => return Err(VmError::IllegalOpcode(instruction)),
             _ => unreachable!("Getting the last 4 bits of a u16 should never reach here"),
         }
+        Ok(self.step_outcome())
     }
 
-    pub fn next_instruction(&mut self) {
-        let instruction = self.memory_read(self.read_register(Register::ProgramCounter));
+    pub fn next_instruction(&mut self) -> Result<StepOutcome, VmError> {
+        if self.is_halted() {
+            return Ok(StepOutcome::Halted);
+        }
+        if let Some(interrupt) = self.pending_interrupt()? {
+            self.initiate_interrupt(interrupt)?;
+        }
+        let instruction = match self.fetch_instruction() {
+            Ok(instruction) => instruction,
+            Err(error) => return self.initiate_exception(error),
+        };
 
-        let new_register_value = self.read_register(Register::ProgramCounter) + 1;
+        let new_register_value = self.read_register(Register::ProgramCounter).wrapping_add(1);
         self.update_register(Register::ProgramCounter, new_register_value);
 
-        self.decode_instruction(instruction);
+        self.decode_instruction(instruction)
     }
 
-    pub fn next_instructions(&mut self, steps: u16) {
+    fn fetch_instruction(&mut self) -> Result<u16, VmError> {
+        let program_counter = self.read_register(Register::ProgramCounter);
+        self.check_access_control(program_counter)?;
+        self.unobserved_memory_read(program_counter)
+    }
+
+    pub fn next_instructions(&mut self, steps: u16) -> Result<StepOutcome, VmError> {
         for _ in 0..steps {
-            self.next_instruction();
+            if self.next_instruction()? == StepOutcome::Halted {
+                return Ok(StepOutcome::Halted);
+            }
+        }
+        Ok(StepOutcome::Continue)
+    }
+
+    pub fn run(&mut self) -> Result<(), VmError> {
+        while self.next_instruction()? != StepOutcome::Halted {}
+        Ok(())
+    }
+
+    /// Like `run`, but stops once `max_instructions` have executed or `timeout` has passed.
+    /// The clock is only checked every few instructions, reads from the console wait at most
+    /// until the timeout.
+    pub fn run_with_limits(&mut self, limits: RunLimits) -> Result<RunOutcome, VmError> {
+        let start = Instant::now();
+        self.input_deadline = limits
+            .timeout
+            .and_then(|timeout| start.checked_add(timeout));
+        let outcome = self.run_within_limits(limits, start);
+        self.input_deadline = None;
+        match outcome {
+            Err(VmError::InputTimedOut) => Ok(RunOutcome::TimedOut),
+            outcome => outcome,
+        }
+    }
+
+    fn run_within_limits(
+        &mut self,
+        limits: RunLimits,
+        start: Instant,
+    ) -> Result<RunOutcome, VmError> {
+        let mut executed_instructions: u64 = 0;
+        loop {
+            if limits
+                .max_instructions
+                .is_some_and(|max_instructions| executed_instructions >= max_instructions)
+            {
+                return Ok(RunOutcome::InstructionLimitReached);
+            }
+            let is_clock_check =
+                executed_instructions.is_multiple_of(INSTRUCTIONS_BETWEEN_CLOCK_CHECKS);
+            if is_clock_check
+                && limits
+                    .timeout
+                    .is_some_and(|timeout| start.elapsed() >= timeout)
+            {
+                return Ok(RunOutcome::TimedOut);
+            }
+            if self.next_instruction()? == StepOutcome::Halted {
+                return Ok(RunOutcome::Halted);
+            }
+            executed_instructions += 1;
         }
     }
 
@@ -201,6 +584,21 @@ impl LC3VirtualMachine {
         self.registers.to_string()
     }
 
+    /// `R0=x0000 ... R7=x0000 PC=x3000 PSR=x8002`, for reports meant to be read.
+    pub fn register_summary(&self) -> String {
+        let mut registers: Vec<String> = (0..8)
+            .filter_map(|index| Register::try_from(index).ok())
+            .enumerate()
+            .map(|(index, register)| format!("R{index}=x{:04X}", self.read_register(register)))
+            .collect();
+        registers.push(format!(
+            "PC=x{:04X}",
+            self.read_register(Register::ProgramCounter)
+        ));
+        registers.push(format!("PSR=x{:04X}", self.processor_status()));
+        registers.join(" ")
+    }
+
     fn sign_extend(mut value_to_extend: u16, amount_of_bits: u16) -> u16 {
         if (value_to_extend >> (amount_of_bits - 1) & 0b1) == 1 {
             value_to_extend |= 0xFFFF << amount_of_bits;
@@ -208,3 +606,246 @@ impl LC3VirtualMachine {
         value_to_extend
     }
 }
+
+#[cfg(test)]
+pub mod test {
+    use std::{
+        cell::RefCell,
+        io::Error,
+        rc::Rc,
+        thread,
+        time::{Duration, Instant},
+    };
+
+    use super::{LC3VirtualMachine, RunLimits, RunOutcome, StepOutcome};
+    use crate::virtual_machine::{
+        console::{BufferConsole, Console},
+        error::VmError,
+        memory_bus::MemoryAccess,
+        register::{PrivilegeMode, Register},
+    };
+
+    #[test]
+    fn keyboard_interrupt_vectors_to_the_service_routine_on_the_supervisor_stack() {
+        let console = BufferConsole::new(b"k");
+        let mut virtual_machine = LC3VirtualMachine::with_console(0x3000, console);
+        virtual_machine.update_processor_status(0x8002);
+        virtual_machine.update_register(Register::R6, 0xFDFF);
+        virtual_machine.memory_write(0x0180, 0x1000).unwrap();
+        virtual_machine.memory_write(0xFE00, 0x4000).unwrap();
+        let add_one_to_register_zero = 0b0001_0000_0010_0001;
+        virtual_machine
+            .memory_write(0x1000, add_one_to_register_zero)
+            .unwrap();
+        virtual_machine.next_instruction().unwrap();
+
+        assert_eq!(
+            virtual_machine.read_register(Register::ProgramCounter),
+            0x1001
+        );
+        assert_eq!(virtual_machine.read_register(Register::R0), 1);
+        assert_eq!(virtual_machine.privilege_mode(), PrivilegeMode::Supervisor);
+        assert_eq!(virtual_machine.processor_status() & 0x0700, 0x0400);
+        assert_eq!(virtual_machine.read_register(Register::R6), 0x2FFE);
+        assert_eq!(virtual_machine.memory_read(0x2FFE).unwrap(), 0x3000);
+        assert_eq!(virtual_machine.memory_read(0x2FFF).unwrap(), 0x8002);
+        assert_eq!(
+            virtual_machine.read_register(Register::SavedUserStackPointer),
+            0xFDFF
+        );
+        assert_eq!(virtual_machine.memory_read(0xFE02).unwrap(), b'k' as u16);
+    }
+
+    #[test]
+    fn illegal_opcode_vectors_to_the_installed_exception_handler() {
+        let mut virtual_machine = LC3VirtualMachine::new(0x3000);
+        virtual_machine.update_register(Register::R6, 0x3000);
+        virtual_machine.memory_write(0x0101, 0x1200).unwrap();
+        let reserved_instruction = 0b1101_0000_0000_0000;
+        virtual_machine
+            .memory_write(0x3000, reserved_instruction)
+            .unwrap();
+        let result = virtual_machine.next_instruction().unwrap();
+
+        assert_eq!(result, StepOutcome::Continue);
+        assert_eq!(
+            virtual_machine.read_register(Register::ProgramCounter),
+            0x1200
+        );
+        assert_eq!(virtual_machine.memory_read(0x2FFE).unwrap(), 0x3001);
+    }
+
+    #[test]
+    fn user_mode_access_to_system_space_without_handler_is_an_error() {
+        let mut virtual_machine = LC3VirtualMachine::new(0x3000);
+        virtual_machine.update_processor_status(0x8002);
+        let load_from_register_zero = 0b0110_0010_0000_0000;
+        virtual_machine
+            .memory_write(0x3000, load_from_register_zero)
+            .unwrap();
+        let result = virtual_machine.next_instruction();
+
+        assert!(matches!(
+            result,
+            Err(VmError::AccessControlViolation(0x0000))
+        ));
+    }
+
+    #[test]
+    fn run_returns_when_the_program_halts() {
+        let mut virtual_machine = LC3VirtualMachine::new(0x3000);
+        let add_one_to_register_zero = 0b0001_0000_0010_0001;
+        let halt = 0xF025;
+        virtual_machine
+            .memory_write(0x3000, add_one_to_register_zero)
+            .unwrap();
+        virtual_machine.memory_write(0x3001, halt).unwrap();
+        virtual_machine.run().unwrap();
+
+        assert!(virtual_machine.is_halted());
+        assert_eq!(virtual_machine.read_register(Register::R0), 1);
+        assert_eq!(
+            virtual_machine.read_register(Register::ProgramCounter),
+            0x3002
+        );
+    }
+
+    #[test]
+    fn run_with_limits_stops_an_endless_loop() {
+        let mut virtual_machine = LC3VirtualMachine::new(0x3000);
+        let branch_to_itself = 0b0000_1111_1111_1111;
+        virtual_machine
+            .memory_write(0x3000, branch_to_itself)
+            .unwrap();
+        let instruction_limit = RunLimits {
+            max_instructions: Some(10),
+            timeout: None,
+        };
+        let time_limit = RunLimits {
+            max_instructions: None,
+            timeout: Some(Duration::from_millis(10)),
+        };
+
+        assert_eq!(
+            virtual_machine.run_with_limits(instruction_limit).unwrap(),
+            RunOutcome::InstructionLimitReached
+        );
+        assert_eq!(
+            virtual_machine.run_with_limits(time_limit).unwrap(),
+            RunOutcome::TimedOut
+        );
+        assert!(!virtual_machine.is_halted());
+    }
+
+    /// Input that never comes, like a terminal nobody types on.
+    struct SilentConsole;
+
+    impl Console for SilentConsole {
+        fn read_byte(&mut self) -> Result<u8, Error> {
+            panic!("a read without a deadline would wait forever");
+        }
+
+        fn poll_byte(&mut self) -> Result<Option<u8>, Error> {
+            Ok(None)
+        }
+
+        fn read_byte_until(&mut self, deadline: Instant) -> Result<Option<u8>, Error> {
+            thread::sleep(deadline.saturating_duration_since(Instant::now()));
+            Ok(None)
+        }
+
+        fn write_byte(&mut self, _byte: u8) -> Result<(), Error> {
+            Ok(())
+        }
+
+        fn flush(&mut self) -> Result<(), Error> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn run_with_limits_stops_a_program_waiting_for_input() {
+        let mut virtual_machine = LC3VirtualMachine::with_console(0x3000, SilentConsole);
+        let get_char = 0xF020;
+        virtual_machine.memory_write(0x3000, get_char).unwrap();
+        let time_limit = RunLimits {
+            max_instructions: None,
+            timeout: Some(Duration::from_millis(10)),
+        };
+
+        assert_eq!(
+            virtual_machine.run_with_limits(time_limit).unwrap(),
+            RunOutcome::TimedOut
+        );
+    }
+
+    #[test]
+    fn memory_hook_sees_data_accesses_but_not_instruction_fetches() {
+        let mut virtual_machine = LC3VirtualMachine::new(0x3000);
+        let load_indirect_to_register_zero = 0b1010_0000_0000_0001;
+        let store_register_zero = 0b0011_0000_0000_0001;
+        virtual_machine
+            .memory_write(0x3000, load_indirect_to_register_zero)
+            .unwrap();
+        virtual_machine
+            .memory_write(0x3001, store_register_zero)
+            .unwrap();
+        virtual_machine.memory_write(0x3002, 0x3004).unwrap();
+        virtual_machine.memory_write(0x3003, 5).unwrap();
+        virtual_machine.memory_write(0x3004, 7).unwrap();
+        let accesses = Rc::new(RefCell::new(Vec::new()));
+        let hook_accesses = Rc::clone(&accesses);
+        virtual_machine.set_memory_hook(move |access| hook_accesses.borrow_mut().push(access));
+        virtual_machine.next_instructions(2).unwrap();
+
+        assert_eq!(
+            *accesses.borrow(),
+            vec![
+                MemoryAccess::Read {
+                    address: 0x3002,
+                    value: 0x3004
+                },
+                MemoryAccess::Read {
+                    address: 0x3004,
+                    value: 7
+                },
+                MemoryAccess::Write {
+                    address: 0x3003,
+                    old_value: Some(5),
+                    new_value: 7
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn clearing_the_clock_enable_bit_halts_the_machine() {
+        let mut virtual_machine = LC3VirtualMachine::new(0x3000);
+        let store_register_zero_indirect = 0b1011_0000_0000_0001;
+        let halt = 0xF025;
+        virtual_machine
+            .memory_write(0x3000, store_register_zero_indirect)
+            .unwrap();
+        virtual_machine.memory_write(0x3001, halt).unwrap();
+        virtual_machine.memory_write(0x3002, 0xFFFE).unwrap();
+        let result = virtual_machine.next_instruction().unwrap();
+
+        assert_eq!(result, StepOutcome::Halted);
+        assert!(virtual_machine.is_halted());
+        assert_eq!(virtual_machine.memory_read(0xFFFE).unwrap(), 0);
+    }
+
+    #[test]
+    fn halted_machine_does_not_execute_more_instructions() {
+        let mut virtual_machine = LC3VirtualMachine::new(0x3000);
+        virtual_machine.memory_write(0x3000, 0xF025).unwrap();
+        virtual_machine.run().unwrap();
+        let result = virtual_machine.next_instruction().unwrap();
+
+        assert_eq!(result, StepOutcome::Halted);
+        assert_eq!(
+            virtual_machine.read_register(Register::ProgramCounter),
+            0x3001
+        );
+    }
+}
@@ -0,0 +1,69 @@
+use std::{cell::Cell, ops::RangeInclusive, rc::Rc};
+
+use super::{
+    console::Console,
+    error::VmError,
+    memory_bus::{Device, MemoryMappedRegister},
+};
+
+pub const CLOCK_ENABLE_BIT: u16 = 1 << 15;
+
+/// The machine control register. The VM keeps a handle on the same value to check the clock.
+#[derive(Clone)]
+pub struct MachineControl {
+    value: Rc<Cell<u16>>,
+}
+
+impl Default for MachineControl {
+    fn default() -> Self {
+        Self {
+            value: Rc::new(Cell::new(CLOCK_ENABLE_BIT)),
+        }
+    }
+}
+
+impl MachineControl {
+    pub fn is_clock_enabled(&self) -> bool {
+        self.value.get() & CLOCK_ENABLE_BIT != 0
+    }
+
+    pub fn stop_clock(&self) {
+        self.value.set(self.value.get() & !CLOCK_ENABLE_BIT);
+    }
+}
+
+impl Device for MachineControl {
+    fn address_range(&self) -> RangeInclusive<u16> {
+        MemoryMappedRegister::MachineControl as u16..=MemoryMappedRegister::MachineControl as u16
+    }
+
+    fn read(&mut self, _memory_address: u16, _console: &mut dyn Console) -> Result<u16, VmError> {
+        Ok(self.value.get())
+    }
+
+    fn write(
+        &mut self,
+        _memory_address: u16,
+        value_to_write: u16,
+        _console: &mut dyn Console,
+    ) -> Result<(), VmError> {
+        self.value.set(value_to_write);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use crate::virtual_machine::{console::BufferConsole, lc3_virtual_machine::LC3VirtualMachine};
+
+    #[test]
+    fn clearing_the_clock_bit_halts_and_keeps_the_other_bits() {
+        let mut virtual_machine = LC3VirtualMachine::with_console(0x3000, BufferConsole::default());
+
+        assert_eq!(virtual_machine.memory_read(0xFFFE).unwrap(), 0x8000);
+        virtual_machine.memory_write(0xFFFE, 0x00FF).unwrap();
+        assert!(virtual_machine.is_halted());
+        assert_eq!(virtual_machine.memory_read(0xFFFE).unwrap(), 0x00FF);
+        assert_eq!(virtual_machine.peek_memory(0xFFFE), None);
+    }
+}
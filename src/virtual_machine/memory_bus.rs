@@ -0,0 +1,201 @@
+use std::ops::RangeInclusive;
+
+use super::{console::Console, error::VmError, lc3_virtual_machine::Interrupt};
+
+pub enum MemoryMappedRegister {
+    KeyBoardStatus = 0xFE00,
+    KeyBoardData = 0xFE02,
+    DisplayStatus = 0xFE04,
+    DisplayData = 0xFE06,
+    MachineControl = 0xFFFE,
+}
+
+/// A data access seen by the memory hook. The old value of a write is unknown on device registers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemoryAccess {
+    Read {
+        address: u16,
+        value: u16,
+    },
+    Write {
+        address: u16,
+        old_value: Option<u16>,
+        new_value: u16,
+    },
+}
+
+impl MemoryAccess {
+    pub fn address(&self) -> u16 {
+        match self {
+            MemoryAccess::Read { address, .. } | MemoryAccess::Write { address, .. } => *address,
+        }
+    }
+}
+
+pub type MemoryHook = Box<dyn FnMut(MemoryAccess)>;
+
+pub trait Device {
+    fn address_range(&self) -> RangeInclusive<u16>;
+
+    fn read(&mut self, memory_address: u16, console: &mut dyn Console) -> Result<u16, VmError>;
+
+    fn write(
+        &mut self,
+        memory_address: u16,
+        value_to_write: u16,
+        console: &mut dyn Console,
+    ) -> Result<(), VmError>;
+
+    fn pending_interrupt(
+        &mut self,
+        _console: &mut dyn Console,
+    ) -> Result<Option<Interrupt>, VmError> {
+        Ok(None)
+    }
+}
+
+pub struct MemoryBus {
+    memory: Vec<u16>,
+    devices: Vec<Box<dyn Device>>,
+}
+
+impl Default for MemoryBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MemoryBus {
+    pub fn new() -> Self {
+        Self {
+            memory: vec![0; 1 << 16],
+            devices: Vec::new(),
+        }
+    }
+
+    /// Devices attached later take precedence over earlier ones on overlapping addresses.
+    pub fn attach_device(&mut self, device: impl Device + 'static) {
+        self.devices.push(Box::new(device));
+    }
+
+    fn device_at(&mut self, memory_address: u16) -> Option<&mut Box<dyn Device>> {
+        self.devices
+            .iter_mut()
+            .rev()
+            .find(|device| device.address_range().contains(&memory_address))
+    }
+
+    /// The stored word, without side effects. None for addresses handled by a device.
+    pub fn peek(&self, memory_address: u16) -> Option<u16> {
+        let is_device_address = self
+            .devices
+            .iter()
+            .any(|device| device.address_range().contains(&memory_address));
+        (!is_device_address).then(|| self.memory[memory_address as usize])
+    }
+
+    pub fn read(&mut self, memory_address: u16, console: &mut dyn Console) -> Result<u16, VmError> {
+        match self.device_at(memory_address) {
+            Some(device) => device.read(memory_address, console),
+            None => Ok(self.memory[memory_address as usize]),
+        }
+    }
+
+    pub fn write(
+        &mut self,
+        memory_address: u16,
+        value_to_write: u16,
+        console: &mut dyn Console,
+    ) -> Result<(), VmError> {
+        match self.device_at(memory_address) {
+            Some(device) => device.write(memory_address, value_to_write, console),
+            None => {
+                self.memory[memory_address as usize] = value_to_write;
+                Ok(())
+            }
+        }
+    }
+
+    pub fn pending_interrupt(
+        &mut self,
+        console: &mut dyn Console,
+    ) -> Result<Option<Interrupt>, VmError> {
+        let mut highest_priority_interrupt: Option<Interrupt> = None;
+        for device in self.devices.iter_mut() {
+            if let Some(interrupt) = device.pending_interrupt(console)? {
+                let is_higher_priority = highest_priority_interrupt
+                    .as_ref()
+                    .is_none_or(|current| interrupt.priority_level > current.priority_level);
+                if is_higher_priority {
+                    highest_priority_interrupt = Some(interrupt);
+                }
+            }
+        }
+        Ok(highest_priority_interrupt)
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use std::ops::RangeInclusive;
+
+    use super::Device;
+    use crate::virtual_machine::{
+        console::{BufferConsole, Console},
+        error::VmError,
+        lc3_virtual_machine::LC3VirtualMachine,
+        register::Register,
+    };
+
+    struct Counter {
+        count: u16,
+    }
+
+    impl Device for Counter {
+        fn address_range(&self) -> RangeInclusive<u16> {
+            0xFE10..=0xFE10
+        }
+
+        fn read(
+            &mut self,
+            _memory_address: u16,
+            _console: &mut dyn Console,
+        ) -> Result<u16, VmError> {
+            self.count += 1;
+            Ok(self.count)
+        }
+
+        fn write(
+            &mut self,
+            _memory_address: u16,
+            value_to_write: u16,
+            _console: &mut dyn Console,
+        ) -> Result<(), VmError> {
+            self.count = value_to_write;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn instructions_reach_attached_devices() {
+        let mut virtual_machine = LC3VirtualMachine::with_console(0x3000, BufferConsole::default());
+        virtual_machine.attach_device(Counter { count: 0 });
+        virtual_machine.memory_write(0xFE10, 41).unwrap();
+        let load_indirect_to_register_zero = 0b1010_0000_0000_0000;
+        virtual_machine
+            .memory_write(0x3000, load_indirect_to_register_zero)
+            .unwrap();
+        virtual_machine.memory_write(0x3001, 0xFE10).unwrap();
+        virtual_machine.next_instruction().unwrap();
+
+        assert_eq!(virtual_machine.read_register(Register::R0), 42);
+    }
+
+    #[test]
+    fn addresses_without_a_device_are_plain_memory() {
+        let mut virtual_machine = LC3VirtualMachine::with_console(0x3000, BufferConsole::default());
+        virtual_machine.memory_write(0xFE10, 7).unwrap();
+
+        assert_eq!(virtual_machine.memory_read(0xFE10).unwrap(), 7);
+    }
+}
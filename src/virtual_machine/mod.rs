@@ -1,4 +1,11 @@
+pub mod console;
+pub mod display;
+pub mod error;
+pub mod file_system;
 pub mod instructions;
+pub mod keyboard;
 pub mod lc3_virtual_machine;
+pub mod machine_control;
+pub mod memory_bus;
 pub mod register;
 pub mod trap;
@@ -1,6 +1,13 @@
 use std::fmt::Display;
 
-pub const AMOUNT_OF_REGISTERS: usize = 10;
+use crate::constants::DEFAULT_SUPERVISOR_STACK_POINTER;
+
+use super::error::VmError;
+
+pub const AMOUNT_OF_REGISTERS: usize = 13;
+const PRIVILEGE_MODE_BIT: u16 = 1 << 15;
+const PRIORITY_LEVEL_MASK: u16 = 0b111 << 8;
+const CONDITION_FLAGS_MASK: u16 = 0b111;
 
 pub enum Flag {
     POSITIVE = 1 << 0,
@@ -20,6 +27,15 @@ pub enum Register {
     R7,
     ProgramCounter,
     ConditionFlag,
+    ProcessorStatus,
+    SavedUserStackPointer,
+    SavedSupervisorStackPointer,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PrivilegeMode {
+    Supervisor,
+    User,
 }
 
 pub struct Registers {
@@ -30,6 +46,8 @@ impl Registers {
     pub fn new(program_counter_start: u16) -> Self {
         let mut registers = vec![0; AMOUNT_OF_REGISTERS];
         registers[Register::ProgramCounter as usize] = program_counter_start;
+        registers[Register::SavedSupervisorStackPointer as usize] =
+            DEFAULT_SUPERVISOR_STACK_POINTER;
         Self { registers }
     }
 
@@ -50,6 +68,54 @@ impl Registers {
             self.update_register(Register::ConditionFlag, Flag::POSITIVE as u16)
         }
     }
+
+    pub fn processor_status(&self) -> u16 {
+        self.read_register(Register::ProcessorStatus)
+            | (self.read_register(Register::ConditionFlag) & CONDITION_FLAGS_MASK)
+    }
+
+    pub fn update_processor_status(&mut self, processor_status: u16) {
+        self.update_register(
+            Register::ProcessorStatus,
+            processor_status & (PRIVILEGE_MODE_BIT | PRIORITY_LEVEL_MASK),
+        );
+        self.update_register(
+            Register::ConditionFlag,
+            processor_status & CONDITION_FLAGS_MASK,
+        );
+    }
+
+    pub fn privilege_mode(&self) -> PrivilegeMode {
+        if self.read_register(Register::ProcessorStatus) & PRIVILEGE_MODE_BIT == 0 {
+            PrivilegeMode::Supervisor
+        } else {
+            PrivilegeMode::User
+        }
+    }
+
+    pub fn update_privilege_mode(&mut self, privilege_mode: PrivilegeMode) {
+        let processor_status = self.read_register(Register::ProcessorStatus) & !PRIVILEGE_MODE_BIT;
+        let privilege_mode_bit = match privilege_mode {
+            PrivilegeMode::Supervisor => 0,
+            PrivilegeMode::User => PRIVILEGE_MODE_BIT,
+        };
+        self.update_register(
+            Register::ProcessorStatus,
+            processor_status | privilege_mode_bit,
+        );
+    }
+
+    pub fn priority_level(&self) -> u16 {
+        (self.read_register(Register::ProcessorStatus) & PRIORITY_LEVEL_MASK) >> 8
+    }
+
+    pub fn update_priority_level(&mut self, priority_level: u16) {
+        let processor_status = self.read_register(Register::ProcessorStatus) & !PRIORITY_LEVEL_MASK;
+        self.update_register(
+            Register::ProcessorStatus,
+            processor_status | ((priority_level << 8) & PRIORITY_LEVEL_MASK),
+        );
+    }
 }
 
 impl Display for Registers {
@@ -64,20 +130,47 @@ impl Display for Registers {
     }
 }
 
-impl From<u16> for Register {
-    fn from(value: u16) -> Self {
+impl Register {
+    /// `R0`-`R7`, `PC`, `COND`, `PSR`, `USP` and `SSP`, in any case.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_uppercase().as_str() {
+            "R0" => Some(Register::R0),
+            "R1" => Some(Register::R1),
+            "R2" => Some(Register::R2),
+            "R3" => Some(Register::R3),
+            "R4" => Some(Register::R4),
+            "R5" => Some(Register::R5),
+            "R6" => Some(Register::R6),
+            "R7" => Some(Register::R7),
+            "PC" => Some(Register::ProgramCounter),
+            "COND" => Some(Register::ConditionFlag),
+            "PSR" => Some(Register::ProcessorStatus),
+            "USP" => Some(Register::SavedUserStackPointer),
+            "SSP" => Some(Register::SavedSupervisorStackPointer),
+            _ => None,
+        }
+    }
+}
+
+impl TryFrom<u16> for Register {
+    type Error = VmError;
+
+    fn try_from(value: u16) -> Result<Self, Self::Error> {
         match value {
-            0 => Register::R0,
-            1 => Register::R1,
-            2 => Register::R2,
-            3 => Register::R3,
-            4 => Register::R4,
-            5 => Register::R5,
-            6 => Register::R6,
-            7 => Register::R7,
-            8 => Register::ProgramCounter,
-            9 => Register::ConditionFlag,
-            _ => panic!("Wrong Register"),
+            0 => Ok(Register::R0),
+            1 => Ok(Register::R1),
+            2 => Ok(Register::R2),
+            3 => Ok(Register::R3),
+            4 => Ok(Register::R4),
+            5 => Ok(Register::R5),
+            6 => Ok(Register::R6),
+            7 => Ok(Register::R7),
+            8 => Ok(Register::ProgramCounter),
+            9 => Ok(Register::ConditionFlag),
+            10 => Ok(Register::ProcessorStatus),
+            11 => Ok(Register::SavedUserStackPointer),
+            12 => Ok(Register::SavedSupervisorStackPointer),
+            _ => Err(VmError::InvalidRegister(value)),
         }
     }
 }
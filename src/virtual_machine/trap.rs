@@ -1,8 +1,11 @@
-use std::io::{Read, Write};
-
 use crate::virtual_machine::register::Register;
 
-use super::lc3_virtual_machine::LC3VirtualMachine;
+use super::{
+    error::VmError,
+    lc3_virtual_machine::{LC3VirtualMachine, StepOutcome},
+};
+
+pub type TrapHandler = Box<dyn FnMut(&mut LC3VirtualMachine) -> Result<StepOutcome, VmError>>;
 
 pub enum Trap {
     GETC,
@@ -13,86 +16,173 @@ pub enum Trap {
     HALT,
 }
 
-impl From<u16> for Trap {
-    fn from(value: u16) -> Self {
+impl TryFrom<u16> for Trap {
+    type Error = VmError;
+
+    fn try_from(value: u16) -> Result<Self, Self::Error> {
         match value {
-            0x20 => Trap::GETC,
-            0x21 => Trap::OUT,
-            0x22 => Trap::PUTS,
-            0x23 => Trap::IN,
-            0x24 => Trap::PUTSP,
-            0x25 => Trap::HALT,
-            _ => panic!("Wrong Trap code"),
+            0x20 => Ok(Trap::GETC),
+            0x21 => Ok(Trap::OUT),
+            0x22 => Ok(Trap::PUTS),
+            0x23 => Ok(Trap::IN),
+            0x24 => Ok(Trap::PUTSP),
+            0x25 => Ok(Trap::HALT),
+            _ => Err(VmError::UnknownTrap(value)),
         }
     }
 }
 
 impl Trap {
-    pub fn execute_trap(&self, virtual_machine: &mut LC3VirtualMachine) {
+    pub fn execute_trap(
+        &self,
+        virtual_machine: &mut LC3VirtualMachine,
+    ) -> Result<StepOutcome, VmError> {
         match self {
-            Trap::GETC => getc(virtual_machine),
-            Trap::HALT => halt(),
-            Trap::IN => in_trap(virtual_machine),
-            Trap::OUT => out(virtual_machine),
-            Trap::PUTS => puts(virtual_machine),
-            Trap::PUTSP => putsp(virtual_machine),
+            Trap::GETC => getc(virtual_machine)?,
+            Trap::HALT => return Ok(halt()),
+            Trap::IN => in_trap(virtual_machine)?,
+            Trap::OUT => out(virtual_machine)?,
+            Trap::PUTS => puts(virtual_machine)?,
+            Trap::PUTSP => putsp(virtual_machine)?,
         }
+        Ok(StepOutcome::Continue)
     }
 }
 
-fn getc(virtual_machine: &mut LC3VirtualMachine) {
-    let mut buffer = [0; 1];
-    std::io::stdin()
-        .read_exact(&mut buffer)
-        .expect("Couldn't read from stdin");
-    virtual_machine.update_register(Register::R0, buffer[0] as u16);
+fn getc(virtual_machine: &mut LC3VirtualMachine) -> Result<(), VmError> {
+    let char = virtual_machine.read_input_byte()?;
+    virtual_machine.update_register(Register::R0, char as u16);
+    Ok(())
 }
 
-fn halt() {
-    std::process::exit(0);
+fn halt() -> StepOutcome {
+    StepOutcome::Halted
 }
 
-fn in_trap(virtual_machine: &mut LC3VirtualMachine) {
-    println!("Enter a character: ");
-    let char = std::io::stdin()
-        .bytes()
-        .next()
-        .and_then(|read_result| read_result.ok())
-        .map(|char| char as u16)
-        .expect("Couldn't read from stdin");
-    virtual_machine.update_register(Register::R0, char);
+fn in_trap(virtual_machine: &mut LC3VirtualMachine) -> Result<(), VmError> {
+    let console = virtual_machine.console();
+    console.write_bytes(b"Enter a character: \n")?;
+    console.flush()?;
+    let char = virtual_machine.read_input_byte()?;
+    virtual_machine.update_register(Register::R0, char as u16);
+    Ok(())
 }
 
-fn out(virtual_machine: &mut LC3VirtualMachine) {
-    print!(
-        "{}",
-        (virtual_machine.read_register(Register::R0) as u8) as char
-    );
+fn out(virtual_machine: &mut LC3VirtualMachine) -> Result<(), VmError> {
+    let char = virtual_machine.read_register(Register::R0) as u8;
+    let console = virtual_machine.console();
+    console.write_byte(char)?;
+    console.flush()?;
+    Ok(())
 }
 
-fn puts(virtual_machine: &mut LC3VirtualMachine) {
+fn puts(virtual_machine: &mut LC3VirtualMachine) -> Result<(), VmError> {
     let mut read_index = virtual_machine.read_register(Register::R0);
-    let mut char = virtual_machine.memory_read(read_index);
+    let mut char = virtual_machine.memory_read(read_index)?;
     while char != 0 {
-        print!("{}", (char as u8) as char);
+        virtual_machine.console().write_byte(char as u8)?;
         read_index = read_index.wrapping_add(1);
-        char = virtual_machine.memory_read(read_index);
+        char = virtual_machine.memory_read(read_index)?;
     }
-    std::io::stdout().flush().expect("Couldn't flush");
+    virtual_machine.console().flush()?;
+    Ok(())
 }
 
-fn putsp(virtual_machine: &mut LC3VirtualMachine) {
+fn putsp(virtual_machine: &mut LC3VirtualMachine) -> Result<(), VmError> {
     let mut read_index = virtual_machine.read_register(Register::R0);
-    let mut char = virtual_machine.memory_read(read_index);
+    let mut char = virtual_machine.memory_read(read_index)?;
     while char != 0 {
         let first_char = char & 0b11111111;
-        print!("{}", (first_char as u8) as char);
+        virtual_machine.console().write_byte(first_char as u8)?;
         let second_char = char >> 8;
         if second_char != 0 {
-            print!("{}", (second_char as u8) as char);
+            virtual_machine.console().write_byte(second_char as u8)?;
         }
         read_index = read_index.wrapping_add(1);
-        char = virtual_machine.memory_read(read_index);
+        char = virtual_machine.memory_read(read_index)?;
+    }
+    virtual_machine.console().flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+pub mod test {
+    use crate::virtual_machine::{
+        console::BufferConsole,
+        error::VmError,
+        lc3_virtual_machine::{LC3VirtualMachine, StepOutcome},
+        register::Register,
+    };
+
+    #[test]
+    fn puts_writes_the_string_to_the_console() {
+        let console = BufferConsole::default();
+        let mut virtual_machine = LC3VirtualMachine::with_console(0x3000, console.clone());
+        for (offset, char) in "hi".bytes().enumerate() {
+            virtual_machine
+                .memory_write(0x4000 + offset as u16, char as u16)
+                .unwrap();
+        }
+        virtual_machine.update_register(Register::R0, 0x4000);
+        let puts = 0xF022;
+        virtual_machine.decode_instruction(puts).unwrap();
+
+        assert_eq!(console.output_string(), "hi");
+    }
+
+    #[test]
+    fn getc_reads_a_char_from_the_console() {
+        let console = BufferConsole::new(b"x");
+        let mut virtual_machine = LC3VirtualMachine::with_console(0x3000, console);
+        let getc = 0xF020;
+        virtual_machine.decode_instruction(getc).unwrap();
+
+        assert_eq!(virtual_machine.read_register(Register::R0), b'x' as u16);
+    }
+
+    #[test]
+    fn registered_trap_handler_runs_for_its_vector() {
+        let mut virtual_machine = LC3VirtualMachine::new(0x3000);
+        virtual_machine.register_trap(0x30, |virtual_machine| {
+            virtual_machine.update_register(Register::R0, 0x1234);
+            Ok(StepOutcome::Continue)
+        });
+        let trap_x30 = 0xF030;
+        virtual_machine.memory_write(0x3000, trap_x30).unwrap();
+        virtual_machine.next_instruction().unwrap();
+
+        assert_eq!(virtual_machine.read_register(Register::R0), 0x1234);
+        assert_eq!(virtual_machine.read_register(Register::R7), 0x3001);
+    }
+
+    #[test]
+    fn registered_trap_handler_overrides_a_built_in_trap() {
+        let console = BufferConsole::default();
+        let mut virtual_machine = LC3VirtualMachine::with_console(0x3000, console.clone());
+        let mut calls = 0;
+        virtual_machine.register_trap(0x25, move |virtual_machine| {
+            calls += 1;
+            virtual_machine
+                .console()
+                .write_bytes(format!("{calls}").as_bytes())?;
+            Ok(StepOutcome::Continue)
+        });
+        let halt = 0xF025;
+        let result = virtual_machine.decode_instruction(halt).unwrap();
+        virtual_machine.decode_instruction(halt).unwrap();
+
+        assert_eq!(result, StepOutcome::Continue);
+        assert_eq!(console.output_string(), "12");
+        assert!(!virtual_machine.is_halted());
+    }
+
+    #[test]
+    fn unregistered_trap_falls_back_to_an_error() {
+        let mut virtual_machine = LC3VirtualMachine::new(0x3000);
+        virtual_machine.register_trap(0x30, |_| Ok(StepOutcome::Continue));
+        virtual_machine.unregister_trap(0x30);
+        let result = virtual_machine.decode_instruction(0xF030);
+
+        assert!(matches!(result, Err(VmError::UnknownTrap(0x30))));
     }
-    std::io::stdout().flush().expect("Couldn't flush");
 }
@@ -1,66 +1,83 @@
 use crate::{
-    constants::{CLIENT_PATH, DEFAULT_PROGRAM_COUNTER_START, SERVER_PATH, STREAM_DATA_SEPARATOR},
-    virtual_machine::{lc3_virtual_machine::LC3VirtualMachine, register::Register},
+    assembler::{disassembler::disassemble, symbol_table::SymbolTable},
+    constants::{
+        CLIENT_PATH, DATAGRAM_BUFFER_SIZE, DEFAULT_PROGRAM_COUNTER_START, SERVER_PATH,
+        STREAM_DATA_SEPARATOR,
+    },
+    debugger::{
+        gdb::{GdbExit, GdbStub},
+        session::{DebugSession, PROGRAM_HALTED_MESSAGE},
+    },
+    loader::{
+        format::ImageFormat,
+        segment::{check_overlaps, entry_point, Segment},
+        validation::{check_warnings, validate_segment},
+    },
+    virtual_machine::{
+        console::StandardConsole,
+        error::VmError,
+        file_system::SandboxedFileSystem,
+        lc3_virtual_machine::{LC3VirtualMachine, RunLimits, RunOutcome, StepOutcome},
+        register::Register,
+    },
 };
-use byteorder::{BigEndian, ReadBytesExt};
 
 use std::{
-    fs::{self, File},
-    io::{stdin, BufRead, BufReader, Error},
+    fs,
+    io::Error,
+    net::{Ipv4Addr, TcpListener},
     os::unix::net::UnixDatagram,
-    path::Path,
 };
 
-pub fn execute_program_from_file(file: &str) -> Result<(), Error> {
-    let reader = receive_file(file)?;
-    let mut virtual_machine = load_reader_file_to_vm_memory(reader)?;
-    loop {
-        virtual_machine.next_instruction();
-    }
+#[derive(Clone, Copy, Default)]
+pub struct ProgramOptions<'a> {
+    pub operating_system_file: Option<&'a str>,
+    pub sandbox_directory: Option<&'a str>,
+    pub entry_file: Option<&'a str>,
+    pub format: Option<ImageFormat>,
+    pub strict: bool,
+    pub limits: RunLimits,
 }
 
-fn print_instructions_for_debugger(file: &str) {
-    println!("Starting debugging of the program {}", file);
+/// When a limit stops the program, the final PC and registers are printed to stderr.
+pub fn execute_program_from_file(
+    files: &[&str],
+    options: ProgramOptions,
+) -> Result<RunOutcome, VmError> {
+    let (mut virtual_machine, symbol_table) = load_program(files, options)?;
+    let outcome = virtual_machine.run_with_limits(options.limits)?;
+    let reason = match outcome {
+        RunOutcome::Halted => return Ok(outcome),
+        RunOutcome::InstructionLimitReached => "instruction limit reached",
+        RunOutcome::TimedOut => "timed out",
+    };
+    let program_counter = virtual_machine.read_register(Register::ProgramCounter);
+    eprintln!(
+        "\nProgram stopped, {reason} at {}",
+        symbol_table.format_address(program_counter)
+    );
+    eprintln!("{}", virtual_machine.register_summary());
+    Ok(outcome)
+}
+
+fn print_instructions_for_debugger(files: &[&str]) {
+    println!("Starting debugging of the program {}", files.join(" "));
     println!("Remember to open the debugger from another terminal with make debugger or cargo run --bin debugger");
 }
 
-pub fn debug_program_from_file(file: &str) -> Result<(), Error> {
-    let reader = receive_file(file)?;
-    let mut virtual_machine = load_reader_file_to_vm_memory(reader)?;
+pub fn debug_program_from_file(files: &[&str], options: ProgramOptions) -> Result<(), VmError> {
+    let (virtual_machine, symbol_table) = load_program(files, options)?;
+    let mut debug_session = DebugSession::new(virtual_machine, symbol_table);
     let _ = fs::remove_file(SERVER_PATH);
     let socket: UnixDatagram = UnixDatagram::bind(SERVER_PATH)?;
-    print_instructions_for_debugger(file);
+    print_instructions_for_debugger(files);
     loop {
-        let mut buffer = [0; 1024];
+        let mut buffer = vec![0; DATAGRAM_BUFFER_SIZE];
         match socket.recv_from(&mut buffer) {
             Ok((size, addr)) => {
                 let command = String::from_utf8_lossy(&buffer[..size]);
-                let command = command.trim();
-                match command {
-                    "n" => {
-                        virtual_machine.next_instruction();
-                        let memory_address =
-                            virtual_machine.read_register(Register::ProgramCounter);
-                        let instruction = virtual_machine.memory_read(memory_address);
-                        let response = format!("instruction: {instruction:#018b}",);
-                        socket.send_to_addr(response.as_bytes(), &addr)?;
-                    }
-                    "r" => {
-                        let response = virtual_machine.state_of_registers();
-                        socket.send_to_addr(response.as_bytes(), &addr)?;
-                    }
-                    _ => match command.parse::<u16>() {
-                        Ok(amount_of_instructions) => {
-                            virtual_machine.next_instructions(amount_of_instructions);
-                            let response =
-                                format!("executed {} instructions", amount_of_instructions);
-                            socket.send_to_addr(response.as_bytes(), &addr)?;
-                        }
-                        Err(_) => {
-                            socket.send_to_addr("Invalid Command".as_bytes(), &addr)?;
-                        }
-                    },
-                }
+                let response = debug_session.execute(command.trim());
+                socket.send_to_addr(response.as_bytes(), &addr)?;
             }
             Err(_) => {
                 socket.send_to("Couldn't receive error".as_bytes(), CLIENT_PATH)?;
@@ -69,6 +86,23 @@ pub fn debug_program_from_file(file: &str) -> Result<(), Error> {
     }
 }
 
+/// Waits for one gdb connection on localhost. After a detach the program runs to the end.
+pub fn debug_program_with_gdb(
+    files: &[&str],
+    options: ProgramOptions,
+    port: u16,
+) -> Result<(), VmError> {
+    let (virtual_machine, _) = load_program(files, options)?;
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))?;
+    println!("Waiting for gdb on port {port}, connect with target remote :{port}");
+    let (stream, _) = listener.accept()?;
+    let mut gdb_stub = GdbStub::new(virtual_machine);
+    match gdb_stub.serve(stream)? {
+        GdbExit::Detached => gdb_stub.into_virtual_machine().run(),
+        GdbExit::Killed | GdbExit::Disconnected => Ok(()),
+    }
+}
+
 fn print_instructions_for_interactive_console() {
     println!("Starting interactive console");
     println!("Instructions: ");
@@ -79,8 +113,7 @@ fn print_instructions_for_interactive_console() {
 pub fn execute_vm_in_interactive_mode() -> Result<(), Error> {
     let mut virtual_machine = LC3VirtualMachine::new(DEFAULT_PROGRAM_COUNTER_START);
     print_instructions_for_interactive_console();
-    for line in stdin().lock().lines() {
-        let line = line?;
+    while let Some(line) = StandardConsole.read_line()? {
         match line.as_str() {
             "r" => {
                 let registers = virtual_machine.state_of_registers();
@@ -91,9 +124,10 @@ pub fn execute_vm_in_interactive_mode() -> Result<(), Error> {
             }
             _ => match u16::from_str_radix(&line, 2) {
                 Ok(instruction) => {
-                    virtual_machine.decode_instruction(instruction);
-                    let instruction = format!("{instruction:#018b}",);
-                    println!("instruction proccess: {}", instruction);
+                    println!(
+                        "{}",
+                        execute_interactive_instruction(&mut virtual_machine, instruction)
+                    )
                 }
                 Err(_) => println!("Wrong instruction format"),
             },
@@ -102,20 +136,96 @@ pub fn execute_vm_in_interactive_mode() -> Result<(), Error> {
     Ok(())
 }
 
-fn receive_file(path: impl AsRef<Path>) -> Result<BufReader<File>, Error> {
-    let file = File::open(path)?;
-    let file_reader = BufReader::new(file);
-    Ok(file_reader)
+/// Typed instructions run without advancing the PC, so PC-relative targets are based on the PC
+/// itself and not on the address after it.
+fn execute_interactive_instruction(
+    virtual_machine: &mut LC3VirtualMachine,
+    instruction: u16,
+) -> String {
+    let program_counter = virtual_machine.read_register(Register::ProgramCounter);
+    let mnemonic = disassemble(
+        instruction,
+        program_counter.wrapping_sub(1),
+        &SymbolTable::new(),
+    );
+    match virtual_machine.decode_instruction(instruction) {
+        Ok(StepOutcome::Halted) => PROGRAM_HALTED_MESSAGE.to_string(),
+        Ok(StepOutcome::Continue) => {
+            format!("instruction proccess: {instruction:#018b} {mnemonic}")
+        }
+        Err(error) => format!("Couldn't execute instruction: {error}"),
+    }
+}
+
+/// Loads every file (and the operating system image) into one machine, refusing overlapping segments.
+/// Suspicious segments are reported as warnings, or refused with `strict`.
+fn load_program(
+    files: &[&str],
+    options: ProgramOptions,
+) -> Result<(LC3VirtualMachine, SymbolTable), VmError> {
+    let mut virtual_machine = LC3VirtualMachine::new(DEFAULT_PROGRAM_COUNTER_START);
+    if let Some(sandbox_directory) = options.sandbox_directory {
+        SandboxedFileSystem::new(sandbox_directory)?.install(&mut virtual_machine);
+    }
+    let mut program_segments = Vec::new();
+    let mut symbol_table = SymbolTable::new();
+    for file in files {
+        program_segments.push(Segment::read(file, options.format)?);
+        symbol_table.merge(SymbolTable::load_for_object_file(file)?);
+    }
+    let program_counter_start = entry_point(&program_segments, options.entry_file)?;
+    let mut warnings: Vec<_> = program_segments
+        .iter()
+        .flat_map(|segment| validate_segment(segment, false))
+        .collect();
+    let mut segments = program_segments;
+    if let Some(operating_system_file) = options.operating_system_file {
+        let operating_system_segment = Segment::read(operating_system_file, None)?;
+        warnings.extend(validate_segment(&operating_system_segment, true));
+        segments.insert(0, operating_system_segment);
+    }
+    for warning in check_warnings(warnings, options.strict)? {
+        eprintln!("warning: {warning}");
+    }
+    check_overlaps(&segments)?;
+    for segment in &segments {
+        segment.load_into(&mut virtual_machine)?;
+    }
+    if options.operating_system_file.is_some() {
+        virtual_machine.enable_operating_system_mode();
+        virtual_machine.enter_user_mode(program_counter_start);
+    } else {
+        virtual_machine.update_register(Register::ProgramCounter, program_counter_start);
+    }
+    Ok((virtual_machine, symbol_table))
 }
 
-fn load_reader_file_to_vm_memory(mut reader: BufReader<File>) -> Result<LC3VirtualMachine, Error> {
-    let program_counter_start = reader.read_u16::<BigEndian>()?;
+#[cfg(test)]
+pub mod test {
+    use super::execute_interactive_instruction;
+    use crate::virtual_machine::{lc3_virtual_machine::LC3VirtualMachine, register::Register};
+
+    #[test]
+    fn interactive_disassembly_matches_where_the_instruction_goes() {
+        let mut virtual_machine = LC3VirtualMachine::new(0x3000);
+        let load_effective_address_three_to_register_zero = 0b1110_0000_0000_0011;
+        let branch_always_two_ahead = 0b0000_1110_0000_0010;
 
-    let mut virtual_machine = LC3VirtualMachine::new(program_counter_start);
-    let mut memory_address = program_counter_start;
-    while let Ok(instruction) = reader.read_u16::<BigEndian>() {
-        virtual_machine.memory_write(memory_address, instruction);
-        memory_address = memory_address.wrapping_add(1);
+        assert_eq!(
+            execute_interactive_instruction(
+                &mut virtual_machine,
+                load_effective_address_three_to_register_zero
+            ),
+            "instruction proccess: 0b1110000000000011 LEA R0, x3003"
+        );
+        assert_eq!(virtual_machine.read_register(Register::R0), 0x3003);
+        assert_eq!(
+            execute_interactive_instruction(&mut virtual_machine, branch_always_two_ahead),
+            "instruction proccess: 0b0000111000000010 BRnzp x3002"
+        );
+        assert_eq!(
+            virtual_machine.read_register(Register::ProgramCounter),
+            0x3002
+        );
     }
-    Ok(virtual_machine)
 }